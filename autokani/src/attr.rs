@@ -0,0 +1,1113 @@
+//! Parsing of the arguments passed to the autokani attributes,
+//! e.g. `#[autokani_func(unwind = 8)]`.
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token::Comma,
+    Attribute, Expr, ExprRange, Fields, FnArg, GenericArgument, Ident, LitInt, LitStr, Pat, Token,
+    Type,
+};
+
+/// Unwind bound used when it can neither be taken from the arguments
+/// nor derived from the generated collections.
+pub(crate) const DEFAULT_UNWIND: usize = 64;
+/// Extra unwindings on top of the largest collection length,
+/// a loop over `n` elements needs `n + 1` unwindings.
+pub(crate) const DEFAULT_UNWIND_MARGIN: usize = 1;
+/// Name of the helper attribute on the fields of `kani_arbitrary` types.
+const FIELD_ATTR: &str = "autokani";
+
+/// Arguments accepted by `autokani_func` and `autokani_method`.
+#[derive(Default)]
+pub(crate) struct HarnessArgs {
+    /// Explicit `#[kani::unwind]` bound of the generated harness.
+    pub unwind: Option<usize>,
+    /// Margin added to the derived unwind bound.
+    pub unwind_margin: Option<usize>,
+    /// Per-argument length limit of the generated collections, e.g. `len(buf = 64)`.
+    pub len: Vec<(Ident, usize)>,
+    /// Per-argument generation mode of strings, e.g. `string(name = ascii)`.
+    pub string: Vec<(Ident, StringMode)>,
+    /// Per-argument range of integers, e.g. `range(index = 0..=255, n = ..)`.
+    pub range: Vec<(Ident, ExprRange)>,
+    /// Harness-wide integer strategy, e.g. `integers = boundary`.
+    pub integers: Option<IntStrategy>,
+    /// Per-argument domain of floats, e.g. `float(x = finite, y = -1e6..1e6)`.
+    pub float: Vec<(Ident, FloatMode)>,
+    /// Harness-wide domain of floats, e.g. `floats = not_nan`.
+    pub floats: Option<FloatMode>,
+    /// Preconditions assumed before the call, e.g. `requires = index < self.len`.
+    pub requires: Vec<Expr>,
+    /// Postconditions asserted after the call, e.g. `ensures = |ret| ret.is_some()`.
+    pub ensures: Vec<Expr>,
+    /// Invariant of `Self` asserted after a call which may modify the receiver,
+    /// e.g. `preserves = Self::is_valid`.
+    pub preserves: Option<Expr>,
+    /// Arguments of the generic parameters, one harness is generated per combination,
+    /// e.g. `instantiate(T = u8, T = i32, N = 4)`.
+    pub instantiate: Vec<(Ident, GenericArgument)>,
+}
+
+/// Arguments accepted by `kani_arbitrary` and `extend_arbitrary`.
+#[derive(Default)]
+pub(crate) struct ArbitraryArgs {
+    /// Integer strategy for the generated fields or constructor arguments.
+    pub integers: Option<IntStrategy>,
+    /// Domain of the floats among the generated fields or constructor arguments.
+    pub floats: Option<FloatMode>,
+    /// Predicate over `&Self` assumed on every generated value, e.g. `invariant = Self::is_valid`.
+    pub invariant: Option<Expr>,
+    /// Also implement `kani::Invariant` with the invariant.
+    pub impl_invariant: bool,
+    /// Constructors of `extend_arbitrary` to choose from, e.g. `ctors = [new, from_parts]`.
+    /// Empty to use the first constructor of the impl block.
+    pub ctors: Vec<Ident>,
+    /// Maximum number of `&mut self` methods called after the constructor, e.g. `steps = 4`.
+    pub steps: Option<usize>,
+    /// Methods which may be called in the steps, all the eligible ones if empty.
+    pub include: Vec<Ident>,
+    /// Methods which are never called in the steps.
+    pub exclude: Vec<Ident>,
+    /// First key which only applies to `extend_arbitrary`.
+    pub impl_key: Option<Ident>,
+}
+
+impl ArbitraryArgs {
+    /// Options shared by every generated field or constructor argument.
+    pub fn field_options(&self) -> ArgOptions {
+        ArgOptions {
+            integers: self.integers.unwrap_or_default(),
+            float: self.floats.clone().unwrap_or_default(),
+            ..ArgOptions::default()
+        }
+    }
+}
+
+impl Parse for ArbitraryArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = ArbitraryArgs::default();
+        parse_args(input, |key, input| match key.to_string().as_str() {
+            "integers" => set_once(&mut args.integers, key, parse_value(input)?),
+            "floats" => set_once(&mut args.floats, key, parse_value(input)?),
+            "invariant" => set_once(&mut args.invariant, key, parse_value(input)?),
+            "ctor" | "ctors" => {
+                args.impl_key.get_or_insert_with(|| key.clone());
+                if !args.ctors.is_empty() {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "duplicate autokani argument, `ctor` and `ctors` choose the constructors",
+                    ));
+                }
+                if key == "ctor" {
+                    args.ctors.push(parse_value(input)?);
+                } else {
+                    args.ctors = parse_ident_list(key, input)?;
+                }
+                Ok(())
+            }
+            "steps" => {
+                args.impl_key.get_or_insert_with(|| key.clone());
+                set_once(
+                    &mut args.steps,
+                    key,
+                    parse_value::<LitInt>(input)?.base10_parse()?,
+                )
+            }
+            "include" | "exclude" => {
+                args.impl_key.get_or_insert_with(|| key.clone());
+                let methods = parse_ident_list(key, input)?;
+                let list = if key == "include" {
+                    &mut args.include
+                } else {
+                    &mut args.exclude
+                };
+                if !list.is_empty() {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("duplicate autokani argument `{}`", key),
+                    ));
+                }
+                *list = methods;
+                Ok(())
+            }
+            "impl_invariant" => {
+                if args.invariant.is_none() {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "`impl_invariant` needs an `invariant` given before it",
+                    ));
+                }
+                args.impl_invariant = true;
+                Ok(())
+            }
+            _ => Err(unknown_key(key)),
+        })?;
+        if args.steps.is_none() {
+            if let Some(method) = args.include.iter().chain(&args.exclude).next() {
+                return Err(syn::Error::new(
+                    method.span(),
+                    "`include` and `exclude` select the methods of `steps`, which is not given",
+                ));
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Parse the `= [a, b, ..]` of `key`, a non-empty list of method names.
+fn parse_ident_list(key: &Ident, input: ParseStream) -> syn::Result<Vec<Ident>> {
+    input.parse::<Token![=]>()?;
+    let content;
+    syn::bracketed!(content in input);
+    let idents = Punctuated::<Ident, Comma>::parse_terminated(&content)?;
+    if idents.is_empty() {
+        return Err(syn::Error::new(
+            key.span(),
+            format!("`{}` needs a method name", key),
+        ));
+    }
+    Ok(idents.into_iter().collect())
+}
+
+/// Arguments accepted by `autokani_impl`.
+#[derive(Default)]
+pub(crate) struct ImplArgs {
+    /// Methods which get a harness, all the methods of the impl block if empty.
+    pub include: Vec<Ident>,
+    /// Methods which never get a harness.
+    pub exclude: Vec<Ident>,
+    /// Arguments of the generic parameters of the impl block, e.g. `instantiate(T = u8)`.
+    pub instantiate: Vec<(Ident, GenericArgument)>,
+    /// Name of the module of the harnesses, e.g. `module = array_queries`.
+    pub module: Option<Ident>,
+}
+
+impl ImplArgs {
+    /// Parse the entry of `key`, shared with the arguments of `autokani_trait`.
+    fn parse_entry(&mut self, key: &Ident, input: ParseStream) -> syn::Result<()> {
+        match key.to_string().as_str() {
+            "include" | "exclude" => {
+                let methods = parse_ident_list(key, input)?;
+                let list = if key == "include" {
+                    &mut self.include
+                } else {
+                    &mut self.exclude
+                };
+                if !list.is_empty() {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("duplicate autokani argument `{}`", key),
+                    ));
+                }
+                *list = methods;
+                Ok(())
+            }
+            "instantiate" => {
+                let entries = parse_instantiate(input)?;
+                self.instantiate.extend(entries);
+                Ok(())
+            }
+            _ => Err(unknown_key(key)),
+        }
+    }
+}
+
+impl Parse for ImplArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = ImplArgs::default();
+        parse_args(input, |key, input| {
+            if key == "module" {
+                return set_once(&mut args.module, key, parse_value(input)?);
+            }
+            args.parse_entry(key, input)
+        })?;
+        Ok(args)
+    }
+}
+
+/// Arguments accepted by `autokani_trait`.
+pub(crate) struct TraitArgs {
+    /// Implementors of the trait whose default methods get a harness, e.g. `impls = [VecStore]`.
+    pub impls: Vec<Type>,
+    /// Selection of the default methods and instantiation of the parameters of the trait.
+    pub methods: ImplArgs,
+}
+
+impl Parse for TraitArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut impls = Vec::new();
+        let mut methods = ImplArgs::default();
+        parse_args(input, |key, input| {
+            if key != "impls" {
+                return methods.parse_entry(key, input);
+            }
+            if !impls.is_empty() {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("duplicate autokani argument `{}`", key),
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            let content;
+            syn::bracketed!(content in input);
+            impls.extend(Punctuated::<Type, Comma>::parse_terminated(&content)?);
+            if impls.is_empty() {
+                return Err(syn::Error::new(key.span(), "`impls` needs a type"));
+            }
+            Ok(())
+        })?;
+        if impls.is_empty() {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "`autokani_trait` needs the implementors to check, e.g. `impls = [VecStore]`",
+            ));
+        }
+        Ok(TraitArgs { impls, methods })
+    }
+}
+
+/// Arguments of the `#[autokani(..)]` helper attribute on the fields of a `kani_arbitrary` type.
+#[derive(Default)]
+pub(crate) struct FieldArgs {
+    /// Length limit of the collections of the field, e.g. `len = 4`.
+    pub len: Option<usize>,
+    /// Generation mode of a string field, e.g. `string = ascii`.
+    pub string: Option<StringMode>,
+    /// Range of an integer field, e.g. `range = 0..16`.
+    pub range: Option<ExprRange>,
+    /// Domain of the floats of the field, e.g. `float = finite`.
+    pub float: Option<FloatMode>,
+    /// Replaces the generation of the field, see [`FieldSource`].
+    pub source: Option<FieldSource>,
+}
+
+/// How a field is produced instead of being generated from its type.
+pub(crate) enum FieldSource {
+    /// A fixed expression, e.g. `value = Vec::new()`.
+    Value(Expr),
+    /// `Default::default()`.
+    Default,
+    /// A call to a generator function, e.g. `with = my_gen`.
+    With(Expr),
+}
+
+impl FieldArgs {
+    /// Parse the `#[autokani(..)]` attributes among `attrs`.
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut args = FieldArgs::default();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident(FIELD_ATTR)) {
+            attr.parse_args_with(|input: ParseStream| args.parse_into(input))?;
+        }
+        Ok(args)
+    }
+
+    /// `opts` of the type, overridden by the options of the field.
+    pub fn options(&self, opts: &ArgOptions) -> ArgOptions {
+        let mut opts = opts.clone();
+        if let Some(len) = self.len {
+            opts.len = Some(len);
+        }
+        if let Some(string) = &self.string {
+            opts.string = string.clone();
+        }
+        if let Some(range) = &self.range {
+            opts.range = Some(range.clone());
+        }
+        if let Some(float) = &self.float {
+            opts.float = float.clone();
+        }
+        opts
+    }
+
+    fn parse_into(&mut self, input: ParseStream) -> syn::Result<()> {
+        parse_args(input, |key, input| {
+            let is_source = matches!(key.to_string().as_str(), "value" | "default" | "with");
+            let has_options = self.len.is_some()
+                || self.string.is_some()
+                || self.range.is_some()
+                || self.float.is_some();
+            if self.source.is_some() || (is_source && has_options) {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!(
+                        "`{}` conflicts with another argument, a field given by `value`, \
+                         `default` or `with` is not generated",
+                        key
+                    ),
+                ));
+            }
+            match key.to_string().as_str() {
+                "len" => set_once(
+                    &mut self.len,
+                    key,
+                    parse_value::<LitInt>(input)?.base10_parse()?,
+                ),
+                "string" => set_once(&mut self.string, key, parse_value(input)?),
+                "range" => {
+                    input.parse::<Token![=]>()?;
+                    set_once(&mut self.range, key, parse_range(input)?)
+                }
+                "float" => set_once(&mut self.float, key, parse_value(input)?),
+                "value" => set_once(
+                    &mut self.source,
+                    key,
+                    FieldSource::Value(parse_value(input)?),
+                ),
+                "default" => set_once(&mut self.source, key, FieldSource::Default),
+                "with" => set_once(
+                    &mut self.source,
+                    key,
+                    FieldSource::With(parse_value(input)?),
+                ),
+                _ => Err(unknown_key(key)),
+            }
+        })
+    }
+}
+
+/// Strip the `#[autokani(..)]` helper attributes from `fields`, they are not real attributes.
+pub(crate) fn strip_field_attrs(fields: &mut Fields) {
+    for field in fields.iter_mut() {
+        field.attrs.retain(|attr| !attr.path.is_ident(FIELD_ATTR));
+    }
+}
+
+/// Generation options of a single argument, shared by everything generated for it
+/// (e.g. both the `Option` and the `Vec` of an `Option<Vec<T>>`).
+#[derive(Clone, Default)]
+pub(crate) struct ArgOptions {
+    /// Length limit of the collections, `ARR_LIMIT` if not given.
+    pub len: Option<usize>,
+    /// How the characters of the strings are chosen.
+    pub string: StringMode,
+    /// Range of the integers, takes precedence over the integer strategy.
+    pub range: Option<ExprRange>,
+    /// How the integers are constrained when no range is given.
+    pub integers: IntStrategy,
+    /// Domain of the floats.
+    pub float: FloatMode,
+}
+
+/// How generated integers are constrained.
+#[derive(Clone, Copy, Default)]
+pub(crate) enum IntStrategy {
+    /// Fully nondeterministic values.
+    Full,
+    /// Magnitude below the default limit for the types wide enough to exceed it.
+    #[default]
+    Bounded,
+    /// Only interesting values: 0, 1, -1, MIN, MAX, MAX - 1 and powers of two.
+    Boundary,
+}
+
+/// Domain of generated floats.
+#[derive(Clone, Default)]
+pub(crate) enum FloatMode {
+    /// Any value, including NaN and infinities.
+    #[default]
+    Any,
+    /// Neither NaN nor infinite.
+    Finite,
+    /// Anything but NaN.
+    NotNan,
+    /// Normal numbers only, see `f64::is_normal`.
+    Normal,
+    /// Values within a range, e.g. `-1e6..1e6`.
+    Range(ExprRange),
+}
+
+impl Parse for FloatMode {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // a range may start with a path too, e.g. `f64::MIN..0.0`
+        let fork = input.fork();
+        let is_mode = fork.parse::<Ident>().is_ok() && (fork.is_empty() || fork.peek(Token![,]));
+        if !is_mode {
+            return parse_range(input).map(FloatMode::Range);
+        }
+        let mode: Ident = input.parse()?;
+        match mode.to_string().as_str() {
+            "any" => Ok(FloatMode::Any),
+            "finite" => Ok(FloatMode::Finite),
+            "not_nan" => Ok(FloatMode::NotNan),
+            "normal" => Ok(FloatMode::Normal),
+            _ => Err(syn::Error::new(
+                mode.span(),
+                format!(
+                    "unknown float mode `{}`, expected one of `any`, `finite`, `not_nan`, \
+                     `normal` or a range such as `-1e6..1e6`",
+                    mode
+                ),
+            )),
+        }
+    }
+}
+
+impl Parse for IntStrategy {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let strategy: Ident = input.parse()?;
+        match strategy.to_string().as_str() {
+            "full" => Ok(IntStrategy::Full),
+            "bounded" => Ok(IntStrategy::Bounded),
+            "boundary" => Ok(IntStrategy::Boundary),
+            _ => Err(syn::Error::new(
+                strategy.span(),
+                format!(
+                    "unknown integer strategy `{}`, expected one of `full`, `bounded` or `boundary`",
+                    strategy
+                ),
+            )),
+        }
+    }
+}
+
+/// How the content of a generated `String` is chosen.
+/// In every mode the length varies from 0 up to the length limit.
+#[derive(Clone, Default)]
+pub(crate) enum StringMode {
+    /// Arbitrary `char`s.
+    #[default]
+    Any,
+    /// ASCII `char`s only.
+    Ascii,
+    /// ASCII digits.
+    Digit,
+    /// ASCII hexadecimal digits.
+    Hex,
+    /// ASCII letters.
+    Alpha,
+    /// ASCII letters and digits.
+    Alnum,
+    /// A custom character class given as a string literal, e.g. `"0-9a-f_"`.
+    /// Each entry is an inclusive range of `char`s.
+    Chars(Vec<(char, char)>),
+    /// Arbitrary bytes that form valid UTF-8; the length limit counts bytes.
+    Utf8,
+}
+
+impl Parse for StringMode {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            let class: LitStr = input.parse()?;
+            return parse_char_class(&class).map(StringMode::Chars);
+        }
+        let mode: Ident = input.parse()?;
+        match mode.to_string().as_str() {
+            "any" => Ok(StringMode::Any),
+            "ascii" => Ok(StringMode::Ascii),
+            "digit" => Ok(StringMode::Digit),
+            "hex" => Ok(StringMode::Hex),
+            "alpha" => Ok(StringMode::Alpha),
+            "alnum" => Ok(StringMode::Alnum),
+            "utf8" => Ok(StringMode::Utf8),
+            _ => Err(syn::Error::new(
+                mode.span(),
+                format!(
+                    "unknown string mode `{}`, expected one of `any`, `ascii`, `digit`, `hex`, \
+                     `alpha`, `alnum`, `utf8` or a string literal of characters",
+                    mode
+                ),
+            )),
+        }
+    }
+}
+
+/// Parse the `= clause` of an `ensures`, whose closure form takes the return value only.
+fn parse_ensures(input: ParseStream) -> syn::Result<Expr> {
+    let clause: Expr = parse_value(input)?;
+    if let Expr::Closure(closure) = &clause {
+        if closure.inputs.len() != 1 {
+            return Err(syn::Error::new_spanned(
+                &closure.inputs,
+                "an `ensures` closure takes exactly one argument, the return value",
+            ));
+        }
+    }
+    Ok(clause)
+}
+
+/// Parse the parenthesized `(T = u8, T = i32, N = 4)` of `instantiate`,
+/// where a parameter may be given several arguments.
+fn parse_instantiate(input: ParseStream) -> syn::Result<Vec<(Ident, GenericArgument)>> {
+    let content;
+    syn::parenthesized!(content in input);
+    let mut entries = Vec::new();
+    parse_args(&content, |param, content| {
+        let arg: GenericArgument = parse_value(content)?;
+        let arg_text = quote::ToTokens::to_token_stream(&arg).to_string();
+        if entries
+            .iter()
+            .any(|(name, prev): &(Ident, GenericArgument)| {
+                name == param && quote::ToTokens::to_token_stream(prev).to_string() == arg_text
+            })
+        {
+            return Err(syn::Error::new_spanned(
+                &arg,
+                format!("duplicate instantiation of `{}`", param),
+            ));
+        }
+        entries.push((param.clone(), arg));
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// Parse a range expression such as `0..=255`, `-8..8` or `..`.
+fn parse_range(input: ParseStream) -> syn::Result<ExprRange> {
+    match input.parse::<Expr>()? {
+        Expr::Range(range) => Ok(range),
+        expr => Err(syn::Error::new_spanned(
+            expr,
+            "expected a range such as `0..=255`, or `..` for no constraint",
+        )),
+    }
+}
+
+/// Parse a character class such as `"0-9a-f_"` into inclusive ranges.
+/// A `-` at the start or the end of the class stands for itself.
+fn parse_char_class(class: &LitStr) -> syn::Result<Vec<(char, char)>> {
+    let chars: Vec<char> = class.value().chars().collect();
+    if chars.is_empty() {
+        return Err(syn::Error::new(class.span(), "empty character class"));
+    }
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if i + 2 < chars.len() && chars[i + 1] == '-' {
+            let (lo, hi) = (chars[i], chars[i + 2]);
+            if lo > hi {
+                return Err(syn::Error::new(
+                    class.span(),
+                    format!("invalid character range `{}-{}`", lo, hi),
+                ));
+            }
+            ranges.push((lo, hi));
+            i += 3;
+        } else {
+            ranges.push((chars[i], chars[i]));
+            i += 1;
+        }
+    }
+    Ok(ranges)
+}
+
+impl HarnessArgs {
+    /// Collect the options given for the argument named `arg_name`.
+    pub fn arg_options(&self, arg_name: &str) -> ArgOptions {
+        ArgOptions {
+            len: find_arg(&self.len, arg_name).copied(),
+            string: find_arg(&self.string, arg_name)
+                .cloned()
+                .unwrap_or_default(),
+            range: find_arg(&self.range, arg_name).cloned(),
+            integers: self.integers.unwrap_or_default(),
+            float: find_arg(&self.float, arg_name)
+                .or(self.floats.as_ref())
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Check that the per-argument settings only name parameters of the function.
+    pub fn check_arg_names(&self, inputs: &Punctuated<FnArg, Comma>) -> syn::Result<()> {
+        let arg_names: Vec<String> = inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                FnArg::Typed(pat_type) => match &*pat_type.pat {
+                    Pat::Ident(pat_ident) => Some(pat_ident.ident.to_string()),
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .collect();
+        let per_arg_names = (self.len.iter().map(|(name, _)| name))
+            .chain(self.string.iter().map(|(name, _)| name))
+            .chain(self.range.iter().map(|(name, _)| name))
+            .chain(self.float.iter().map(|(name, _)| name));
+        for name in per_arg_names {
+            if !arg_names.iter().any(|arg_name| name == arg_name) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("no argument named `{}`", name),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn find_arg<'a, T>(entries: &'a [(Ident, T)], arg_name: &str) -> Option<&'a T> {
+    entries
+        .iter()
+        .find(|(name, _)| name == arg_name)
+        .map(|(_, value)| value)
+}
+
+impl Parse for HarnessArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = HarnessArgs::default();
+        parse_args(input, |key, input| match key.to_string().as_str() {
+            "unwind" => set_once(
+                &mut args.unwind,
+                key,
+                parse_value::<LitInt>(input)?.base10_parse()?,
+            ),
+            "unwind_margin" => set_once(
+                &mut args.unwind_margin,
+                key,
+                parse_value::<LitInt>(input)?.base10_parse()?,
+            ),
+            "len" => {
+                let entries =
+                    parse_per_arg(input, |input| input.parse::<LitInt>()?.base10_parse())?;
+                args.len.extend(entries);
+                Ok(())
+            }
+            "string" => {
+                let entries = parse_per_arg(input, StringMode::parse)?;
+                args.string.extend(entries);
+                Ok(())
+            }
+            "integers" => set_once(&mut args.integers, key, parse_value(input)?),
+            "floats" => set_once(&mut args.floats, key, parse_value(input)?),
+            "float" => {
+                let entries = parse_per_arg(input, FloatMode::parse)?;
+                args.float.extend(entries);
+                Ok(())
+            }
+            "requires" => {
+                args.requires.push(parse_value(input)?);
+                Ok(())
+            }
+            "ensures" => {
+                args.ensures.push(parse_ensures(input)?);
+                Ok(())
+            }
+            "range" => {
+                let entries = parse_per_arg(input, parse_range)?;
+                args.range.extend(entries);
+                Ok(())
+            }
+            "preserves" => set_once(&mut args.preserves, key, parse_value(input)?),
+            "instantiate" => {
+                let entries = parse_instantiate(input)?;
+                args.instantiate.extend(entries);
+                Ok(())
+            }
+            _ => Err(unknown_key(key)),
+        })?;
+        Ok(args)
+    }
+}
+
+/// Parse a comma separated list of `key ...` entries.
+///
+/// `parse_entry` is called with the key of every entry and is responsible for
+/// consuming the rest of the entry, e.g. `= value` or a parenthesized list.
+pub(crate) fn parse_args<F>(input: ParseStream, mut parse_entry: F) -> syn::Result<()>
+where
+    F: FnMut(&Ident, ParseStream) -> syn::Result<()>,
+{
+    while !input.is_empty() {
+        let key: Ident = input.parse()?;
+        parse_entry(&key, input)?;
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(())
+}
+
+/// Parse a parenthesized `(arg = value, ...)` list of per-argument settings.
+pub(crate) fn parse_per_arg<T, F>(
+    input: ParseStream,
+    mut parse_value: F,
+) -> syn::Result<Vec<(Ident, T)>>
+where
+    F: FnMut(ParseStream) -> syn::Result<T>,
+{
+    let content;
+    syn::parenthesized!(content in input);
+    let mut entries: Vec<(Ident, T)> = Vec::new();
+    parse_args(&content, |name, content| {
+        if entries.iter().any(|(seen, _)| seen == name) {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("duplicate setting for argument `{}`", name),
+            ));
+        }
+        content.parse::<Token![=]>()?;
+        entries.push((name.clone(), parse_value(content)?));
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// Parse the `= value` part of a `key = value` entry.
+pub(crate) fn parse_value<T: Parse>(input: ParseStream) -> syn::Result<T> {
+    input.parse::<Token![=]>()?;
+    input.parse()
+}
+
+/// Store `value` into `slot`, rejecting keys that are given more than once.
+pub(crate) fn set_once<T>(slot: &mut Option<T>, key: &Ident, value: T) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(syn::Error::new(
+            key.span(),
+            format!("duplicate autokani argument `{}`", key),
+        ));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+pub(crate) fn unknown_key(key: &Ident) -> syn::Error {
+    syn::Error::new(key.span(), format!("unknown autokani argument `{}`", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_of<T: Parse>(text: &str) -> String {
+        match syn::parse_str::<T>(text) {
+            Ok(_) => panic!("`{}` was accepted", text),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    /// The error of `check_arg_names` for `args` on `fn f(&self, buf: &[u8], x: f32)`.
+    fn arg_name_error(args: &str) -> Option<String> {
+        let func: syn::ItemFn = syn::parse_str("fn f(&self, buf: &[u8], x: f32) {}").unwrap();
+        let args: HarnessArgs = syn::parse_str(args).unwrap();
+        args.check_arg_names(&func.sig.inputs)
+            .err()
+            .map(|err| err.to_string())
+    }
+
+    #[test]
+    fn unwind_args() {
+        let args: HarnessArgs = syn::parse_str("unwind = 8").unwrap();
+        assert_eq!(args.unwind, Some(8));
+        assert_eq!(syn::parse_str::<HarnessArgs>("").unwrap().unwind, None);
+        assert_eq!(
+            error_of::<HarnessArgs>("unwind = 8, unwind = 9"),
+            "duplicate autokani argument `unwind`"
+        );
+        assert_eq!(
+            error_of::<HarnessArgs>("unwnd = 8"),
+            "unknown autokani argument `unwnd`"
+        );
+        assert_eq!(
+            error_of::<HarnessArgs>("unwind = eight"),
+            "expected integer literal"
+        );
+    }
+
+    #[test]
+    fn len_settings() {
+        let args: HarnessArgs = syn::parse_str("len(buf = 64, items = 3)").unwrap();
+        assert_eq!(args.arg_options("buf").len, Some(64));
+        assert_eq!(args.arg_options("items").len, Some(3));
+        assert_eq!(args.arg_options("other").len, None);
+        assert_eq!(
+            error_of::<HarnessArgs>("len(buf = 4, buf = 8)"),
+            "duplicate setting for argument `buf`"
+        );
+        assert_eq!(arg_name_error("len(buf = 4)"), None);
+        assert_eq!(
+            arg_name_error("len(nope = 4)").unwrap(),
+            "no argument named `nope`"
+        );
+    }
+
+    fn char_class(class: &str) -> syn::Result<Vec<(char, char)>> {
+        parse_char_class(&LitStr::new(class, proc_macro2::Span::call_site()))
+    }
+
+    #[test]
+    fn string_modes() {
+        let args: HarnessArgs =
+            syn::parse_str(r#"string(name = ascii, id = "0-9a-f", raw = utf8)"#).unwrap();
+        assert!(matches!(args.arg_options("name").string, StringMode::Ascii));
+        assert!(matches!(
+            args.arg_options("id").string,
+            StringMode::Chars(_)
+        ));
+        assert!(matches!(args.arg_options("raw").string, StringMode::Utf8));
+        assert!(matches!(args.arg_options("other").string, StringMode::Any));
+        assert!(error_of::<HarnessArgs>("string(name = hexa)")
+            .starts_with("unknown string mode `hexa`"));
+        assert_eq!(
+            arg_name_error("string(nope = ascii)").unwrap(),
+            "no argument named `nope`"
+        );
+    }
+
+    #[test]
+    fn char_class_ranges_and_literal_dashes() {
+        assert_eq!(
+            char_class("0-9a-f_").unwrap(),
+            [('0', '9'), ('a', 'f'), ('_', '_')]
+        );
+        assert_eq!(char_class("-a").unwrap(), [('-', '-'), ('a', 'a')]);
+        assert_eq!(char_class("a-").unwrap(), [('a', 'a'), ('-', '-')]);
+        assert_eq!(
+            char_class("z-a").unwrap_err().to_string(),
+            "invalid character range `z-a`"
+        );
+        assert_eq!(
+            char_class("").unwrap_err().to_string(),
+            "empty character class"
+        );
+    }
+
+    #[test]
+    fn range_settings() {
+        let args: HarnessArgs = syn::parse_str("range(index = 0..=255, n = ..)").unwrap();
+        let index = args.arg_options("index").range.unwrap();
+        assert_eq!(
+            quote::ToTokens::to_token_stream(&index).to_string(),
+            quote::quote! { 0..=255 }.to_string()
+        );
+        let n = args.arg_options("n").range.unwrap();
+        assert!(n.from.is_none() && n.to.is_none());
+        assert!(args.arg_options("other").range.is_none());
+        assert_eq!(
+            error_of::<HarnessArgs>("range(n = 5)"),
+            "expected a range such as `0..=255`, or `..` for no constraint"
+        );
+        assert_eq!(arg_name_error("range(buf = ..)"), None);
+        assert_eq!(
+            arg_name_error("range(nope = 0..3)").unwrap(),
+            "no argument named `nope`"
+        );
+    }
+
+    #[test]
+    fn integer_strategies() {
+        let strategy = |text: &str| syn::parse_str::<HarnessArgs>(text).unwrap().integers;
+        assert!(matches!(
+            strategy("integers = full"),
+            Some(IntStrategy::Full)
+        ));
+        assert!(matches!(
+            strategy("integers = bounded"),
+            Some(IntStrategy::Bounded)
+        ));
+        assert!(matches!(
+            strategy("integers = boundary"),
+            Some(IntStrategy::Boundary)
+        ));
+        assert!(matches!(
+            syn::parse_str::<HarnessArgs>("")
+                .unwrap()
+                .arg_options("x")
+                .integers,
+            IntStrategy::Bounded
+        ));
+        let args: ArbitraryArgs = syn::parse_str("integers = boundary").unwrap();
+        assert!(matches!(
+            args.field_options().integers,
+            IntStrategy::Boundary
+        ));
+        assert!(error_of::<HarnessArgs>("integers = edge")
+            .starts_with("unknown integer strategy `edge`"));
+        assert_eq!(
+            error_of::<HarnessArgs>("integers = full, integers = full"),
+            "duplicate autokani argument `integers`"
+        );
+    }
+
+    #[test]
+    fn float_modes() {
+        let args: HarnessArgs = syn::parse_str(
+            "floats = finite, float(ratio = 0.0..=1.0, x = not_nan, low = f64::MIN..0.0)",
+        )
+        .unwrap();
+        assert!(matches!(args.arg_options("x").float, FloatMode::NotNan));
+        assert!(matches!(
+            args.arg_options("ratio").float,
+            FloatMode::Range(_)
+        ));
+        assert!(matches!(args.arg_options("low").float, FloatMode::Range(_)));
+        assert!(matches!(args.arg_options("other").float, FloatMode::Finite));
+        let args: ArbitraryArgs = syn::parse_str("floats = normal").unwrap();
+        assert!(matches!(args.field_options().float, FloatMode::Normal));
+        assert!(error_of::<HarnessArgs>("floats = real").starts_with("unknown float mode `real`"));
+        assert_eq!(arg_name_error("float(x = any)"), None);
+        assert_eq!(
+            arg_name_error("float(nope = finite)").unwrap(),
+            "no argument named `nope`"
+        );
+    }
+
+    #[test]
+    fn requires_clauses() {
+        let args: HarnessArgs =
+            syn::parse_str("requires = index < self.len, requires = divisor != 0").unwrap();
+        assert_eq!(args.requires.len(), 2);
+        assert!(error_of::<HarnessArgs>("requires").starts_with("expected `=`"));
+    }
+
+    #[test]
+    fn ensures_clauses() {
+        let args: HarnessArgs =
+            syn::parse_str("ensures = |ret| ret.is_some(), ensures = result > 0").unwrap();
+        assert_eq!(args.ensures.len(), 2);
+        assert_eq!(
+            error_of::<HarnessArgs>("ensures = |ret, x| ret == x"),
+            "an `ensures` closure takes exactly one argument, the return value"
+        );
+    }
+
+    #[test]
+    fn instantiations() {
+        let args: HarnessArgs =
+            syn::parse_str("instantiate(T = u8, T = Vec<i32>, N = 4), instantiate(U = bool)")
+                .unwrap();
+        let params: Vec<String> = args
+            .instantiate
+            .iter()
+            .map(|(param, _)| param.to_string())
+            .collect();
+        assert_eq!(params, ["T", "T", "N", "U"]);
+        assert_eq!(
+            error_of::<HarnessArgs>("instantiate(T = u8, T = u8)"),
+            "duplicate instantiation of `T`"
+        );
+    }
+
+    fn field_args(attrs: &str) -> syn::Result<FieldArgs> {
+        let field: syn::FieldsNamed =
+            syn::parse_str(&format!("{{ {} field: u8 }}", attrs)).unwrap();
+        FieldArgs::from_attrs(&field.named[0].attrs)
+    }
+
+    #[test]
+    fn field_attributes() {
+        let args =
+            field_args("#[autokani(range = 0..16, len = 4)] #[autokani(float = finite)]").unwrap();
+        let opts = args.options(&ArgOptions {
+            len: Some(8),
+            integers: IntStrategy::Full,
+            ..ArgOptions::default()
+        });
+        assert_eq!(opts.len, Some(4));
+        assert!(opts.range.is_some());
+        assert!(matches!(opts.float, FloatMode::Finite));
+        assert!(matches!(opts.integers, IntStrategy::Full));
+        assert!(matches!(
+            field_args("#[autokani(value = Vec::new())]")
+                .unwrap()
+                .source,
+            Some(FieldSource::Value(_))
+        ));
+        assert!(matches!(
+            field_args("#[autokani(default)]").unwrap().source,
+            Some(FieldSource::Default)
+        ));
+        assert!(matches!(
+            field_args("#[doc = \"not ours\"] #[autokani(with = gen::small)]")
+                .unwrap()
+                .source,
+            Some(FieldSource::With(_))
+        ));
+        let error = |attrs: &str| field_args(attrs).err().unwrap().to_string();
+        assert_eq!(
+            error("#[autokani(len = 4, default)]"),
+            "`default` conflicts with another argument, a field given by `value`, \
+             `default` or `with` is not generated"
+        );
+        assert_eq!(
+            error("#[autokani(default)] #[autokani(with = f)]"),
+            "`with` conflicts with another argument, a field given by `value`, \
+             `default` or `with` is not generated"
+        );
+        assert_eq!(
+            error("#[autokani(size = 4)]"),
+            "unknown autokani argument `size`"
+        );
+    }
+
+    #[test]
+    fn invariant_args() {
+        let args: ArbitraryArgs =
+            syn::parse_str("invariant = Self::is_valid, impl_invariant").unwrap();
+        assert!(args.invariant.is_some() && args.impl_invariant);
+        let args: ArbitraryArgs = syn::parse_str("invariant = |v: &Self| v.len > 0").unwrap();
+        assert!(!args.impl_invariant);
+        assert_eq!(
+            error_of::<ArbitraryArgs>("impl_invariant"),
+            "`impl_invariant` needs an `invariant` given before it"
+        );
+    }
+
+    #[test]
+    fn constructor_args() {
+        let args: ArbitraryArgs = syn::parse_str("ctor = new").unwrap();
+        assert_eq!(args.ctors, ["new"]);
+        assert_eq!(args.impl_key.unwrap(), "ctor");
+        let args: ArbitraryArgs = syn::parse_str("ctors = [new, from_parts]").unwrap();
+        assert_eq!(args.ctors, ["new", "from_parts"]);
+        assert_eq!(
+            error_of::<ArbitraryArgs>("ctor = new, ctors = [from_parts]"),
+            "duplicate autokani argument, `ctor` and `ctors` choose the constructors"
+        );
+        assert_eq!(
+            error_of::<ArbitraryArgs>("ctor = new, ctor = from_parts"),
+            "duplicate autokani argument, `ctor` and `ctors` choose the constructors"
+        );
+    }
+
+    #[test]
+    fn step_args() {
+        let args: ArbitraryArgs =
+            syn::parse_str("steps = 3, include = [push, pop], exclude = [pop]").unwrap();
+        assert_eq!(args.steps, Some(3));
+        assert_eq!(args.include, ["push", "pop"]);
+        assert_eq!(args.exclude, ["pop"]);
+        assert_eq!(
+            error_of::<ArbitraryArgs>("include = [push]"),
+            "`include` and `exclude` select the methods of `steps`, which is not given"
+        );
+        assert_eq!(
+            error_of::<ArbitraryArgs>("steps = 2, exclude = [clear], exclude = [pop]"),
+            "duplicate autokani argument `exclude`"
+        );
+        error_of::<ArbitraryArgs>("steps = 2, include = []");
+    }
+
+    #[test]
+    fn impl_args() {
+        let args: ImplArgs =
+            syn::parse_str("include = [a, b], module = queries, instantiate(T = u8)").unwrap();
+        assert_eq!(args.include, ["a", "b"]);
+        assert_eq!(args.module.unwrap(), "queries");
+        assert_eq!(args.instantiate.len(), 1);
+        assert_eq!(
+            error_of::<ImplArgs>("include = []"),
+            "`include` needs a method name"
+        );
+        assert_eq!(
+            error_of::<ImplArgs>("module = a, module = b"),
+            "duplicate autokani argument `module`"
+        );
+    }
+
+    #[test]
+    fn trait_args() {
+        let args: TraitArgs =
+            syn::parse_str("impls = [VecStore, Slot<u8>], exclude = [a]").unwrap();
+        assert_eq!(args.impls.len(), 2);
+        assert_eq!(args.methods.exclude, ["a"]);
+        assert_eq!(
+            error_of::<TraitArgs>("include = [a]"),
+            "`autokani_trait` needs the implementors to check, e.g. `impls = [VecStore]`"
+        );
+        assert_eq!(
+            error_of::<TraitArgs>("impls = [A], module = m"),
+            "unknown autokani argument `module`"
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! User-written contract clauses of the generated harnesses, e.g. `requires`.
+//!
+//! The clauses are either checked by the harness itself, or attached to the target
+//! function as Kani function contracts in contract mode (`autokani_contract`).
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+use syn::{Expr, FnArg, ItemFn, Pat, ReturnType, Type};
+
+/// Name the harness binds the receiver of a method to.
+pub(crate) const SELF_RECEIVER: &str = "self_receiver";
+
+/// Rewrite `self` in a clause to the harness binding of the receiver,
+/// so that clauses can be written with the real parameter names.
+pub(crate) fn bind_self(tokens: TokenStream) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Ident(ident) if ident == "self" => {
+                TokenTree::Ident(Ident::new(SELF_RECEIVER, ident.span()))
+            }
+            TokenTree::Group(group) => {
+                let mut renamed = Group::new(group.delimiter(), bind_self(group.stream()));
+                renamed.set_span(group.span());
+                TokenTree::Group(renamed)
+            }
+            token => token,
+        })
+        .collect()
+}
+
+/// Assume the `requires` clauses, once all the arguments are initialized.
+pub(crate) fn assume_requires(requires: &[Expr]) -> TokenStream {
+    let clauses = requires.iter().map(|clause| bind_self(quote! { #clause }));
+    quote! {
+        #(kani::assume(#clauses);)*
+    }
+}
+
+/// Bind the return value of `call` to `result` and assert the `ensures` clauses on it,
+/// followed by the `post_checks` statements.
+///
+/// A clause is either an expression over `result` or a closure taking a reference
+/// to the return value, e.g. `|ret| ret.is_some()`. The `old(expr)` calls of the
+/// clauses are replaced by clones of `expr` taken right before the call.
+pub(crate) fn assert_ensures(
+    call: TokenStream,
+    ensures: &[Expr],
+    post_checks: TokenStream,
+) -> TokenStream {
+    if ensures.is_empty() {
+        if mentions_ident(post_checks.clone(), "result") {
+            return quote! {
+                let result = #call;
+                #post_checks
+            };
+        }
+        return quote! {
+            let _ = #call;
+            #post_checks
+        };
+    }
+    let mut snapshots = Vec::new();
+    let asserts: Vec<TokenStream> = ensures
+        .iter()
+        .map(|clause| {
+            let msg = format!("autokani ensures: {}", clause_text(clause));
+            let cond = match clause {
+                Expr::Closure(closure) => {
+                    let ret_pat = &closure.inputs[0];
+                    let body = &closure.body;
+                    quote! {
+                        {
+                            let #ret_pat = &result;
+                            #body
+                        }
+                    }
+                }
+                _ => quote! { #clause },
+            };
+            let cond = bind_self(replace_old(cond, &mut snapshots));
+            quote! {
+                kani::assert(#cond, #msg);
+            }
+        })
+        .collect();
+    let snapshot_stmts = snapshots.iter().enumerate().map(|(i, (expr, span))| {
+        let old_ident = old_ident(i);
+        let expr = bind_self(expr.clone());
+        quote_spanned! {*span=>
+            let #old_ident = autokani_old_clone(&(#expr));
+        }
+    });
+    let clone_helper = if snapshots.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn autokani_old_clone<T: ::core::clone::Clone>(value: &T) -> T {
+                value.clone()
+            }
+        }
+    };
+    quote! {
+        #clone_helper
+        #(#snapshot_stmts)*
+        let result = #call;
+        #(#asserts)*
+        #post_checks
+    }
+}
+
+/// Assert the invariant of `Self` on `target`, a `&Self` expression.
+///
+/// With `preserves = pred`, the invariant is `pred`. Otherwise it is the `kani::Invariant`
+/// impl of `Self`, if there is one, which is selected through autoref specialization:
+/// `check_invariant` of the first trait applies to the probe itself and only exists when
+/// `Self: kani::Invariant`, the second one applies to a reference to the probe and always
+/// exists, doing nothing.
+pub(crate) fn assert_invariant(preserves: Option<&Expr>, target: TokenStream) -> TokenStream {
+    if let Some(pred) = preserves {
+        let msg = format!("autokani preserves: {}", clause_text(pred));
+        return quote! {
+            kani::assert((#pred)(#target), #msg);
+        };
+    }
+    quote! {
+        {
+            struct AutokaniInvariantProbe<'a, T>(&'a T);
+            trait AutokaniCheckInvariant {
+                fn check_invariant(&self);
+            }
+            impl<T: kani::Invariant> AutokaniCheckInvariant for AutokaniInvariantProbe<'_, T> {
+                fn check_invariant(&self) {
+                    kani::assert(
+                        kani::Invariant::is_safe(self.0),
+                        "autokani preserves: the `kani::Invariant` of `Self`",
+                    );
+                }
+            }
+            trait AutokaniNoInvariant {
+                fn check_invariant(&self);
+            }
+            impl<T> AutokaniNoInvariant for &AutokaniInvariantProbe<'_, T> {
+                fn check_invariant(&self) {}
+            }
+            (&AutokaniInvariantProbe(#target)).check_invariant();
+        }
+    }
+}
+
+fn old_ident(index: usize) -> Ident {
+    quote::format_ident!("old_{}", index)
+}
+
+/// Replace every `old(expr)` call by the snapshot variable of `expr`,
+/// recording `expr` along with the span of the call.
+fn replace_old(tokens: TokenStream, snapshots: &mut Vec<(TokenStream, Span)>) -> TokenStream {
+    let mut replaced = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident == "old" => {
+                // `x.old(..)` and `path::old(..)` are not snapshots
+                let is_path_or_method = matches!(replaced.last(), Some(TokenTree::Punct(punct)) if punct.as_char() == '.' || punct.as_char() == ':');
+                match tokens.peek() {
+                    Some(TokenTree::Group(group))
+                        if group.delimiter() == Delimiter::Parenthesis && !is_path_or_method =>
+                    {
+                        snapshots.push((group.stream(), ident.span()));
+                        let old_ident = old_ident(snapshots.len() - 1);
+                        replaced.push(TokenTree::Ident(old_ident));
+                        tokens.next();
+                    }
+                    _ => replaced.push(TokenTree::Ident(ident)),
+                }
+            }
+            TokenTree::Group(group) => {
+                let mut inner =
+                    Group::new(group.delimiter(), replace_old(group.stream(), snapshots));
+                inner.set_span(group.span());
+                replaced.push(TokenTree::Group(inner));
+            }
+            token => replaced.push(token),
+        }
+    }
+    replaced.into_iter().collect()
+}
+
+/// Render a clause for an assertion message, without the spaces `to_string` puts
+/// around punctuation, e.g. `|ret| ret.is_some()` rather than `| ret | ret . is_some ()`.
+fn clause_text(clause: &Expr) -> String {
+    match clause {
+        Expr::Closure(closure) => {
+            let inputs = &closure.inputs;
+            let body = &closure.body;
+            format!(
+                "|{}| {}",
+                tidy_tokens(quote! { #inputs }),
+                tidy_tokens(quote! { #body })
+            )
+        }
+        _ => tidy_tokens(quote! { #clause }),
+    }
+}
+
+fn tidy_tokens(tokens: TokenStream) -> String {
+    let raw = tokens.to_string().replace('\n', " ");
+    let mut text: Vec<char> = Vec::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    // position right after the last prefix operator glued to its operand, e.g. `!` in `!*ret`
+    let mut after_prefix = None;
+    while let Some(c) = chars.next() {
+        if c == ' ' {
+            let prev = text.last().copied();
+            let next = chars.peek().copied();
+            let after_open = matches!(prev, Some('.' | '(' | '[' | '!'))
+                || (prev == Some(':') && text.iter().rev().nth(1) == Some(&':'));
+            let before_close = matches!(next, Some('.' | ',' | ')' | ']' | '?' | ':'));
+            let operand = |c: char| c.is_alphanumeric() || matches!(c, '_' | ')' | ']');
+            let call_or_index = matches!(next, Some('(' | '[')) && prev.is_some_and(operand);
+            // a prefix operator is a token of its own, or follows another prefix operator,
+            // and does not follow an operand
+            let follows_prefix = after_prefix.is_some_and(|pos| pos + 1 == text.len());
+            let unary = matches!(prev, Some('*' | '&' | '-'))
+                && (follows_prefix
+                    || matches!(text.iter().rev().nth(1), None | Some(' ' | '(' | '[')))
+                && !text
+                    .iter()
+                    .rev()
+                    .skip(1)
+                    .find(|c| **c != ' ')
+                    .is_some_and(|c| operand(*c));
+            if unary || prev == Some('!') {
+                after_prefix = Some(text.len());
+            }
+            if after_open || before_close || call_or_index || unary {
+                continue;
+            }
+        }
+        text.push(c);
+    }
+    text.into_iter().collect()
+}
+
+/// Kani function contract attributes of the target function in contract mode.
+///
+/// The clauses keep their real parameter names since they are attached to the function
+/// itself, and Kani handles `old(expr)` on its own. `modifies` lists the `&mut` and
+/// `*mut` parameters.
+pub(crate) fn contract_attrs(func: &ItemFn, requires: &[Expr], ensures: &[Expr]) -> TokenStream {
+    let ret_type = match &func.sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+    let ensures = ensures.iter().map(|clause| match clause {
+        Expr::Closure(closure) => {
+            let body = &closure.body;
+            match &closure.inputs[0] {
+                Pat::Type(_) => quote! { #clause },
+                ret_pat => quote! { |#ret_pat: &#ret_type| #body },
+            }
+        }
+        _ if !mentions_ident(quote! { #clause }, "result") => quote! { |_: &#ret_type| #clause },
+        // Kani passes a reference to the return value, while clauses over `result` use the value
+        _ => quote! {
+            |result: &#ret_type| {
+                let result = ::core::clone::Clone::clone(result);
+                #clause
+            }
+        },
+    });
+    let modified: Vec<TokenStream> = func
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Receiver(receiver) => match (&receiver.reference, &receiver.mutability) {
+                (Some(_), Some(_)) => Some(quote! { self }),
+                _ => None,
+            },
+            FnArg::Typed(pat_type) => {
+                let is_mut_ref = match &*pat_type.ty {
+                    Type::Reference(type_ref) => type_ref.mutability.is_some(),
+                    Type::Ptr(type_ptr) => type_ptr.mutability.is_some(),
+                    _ => false,
+                };
+                match &*pat_type.pat {
+                    Pat::Ident(pat_ident) if is_mut_ref => {
+                        let ident = &pat_ident.ident;
+                        Some(quote! { #ident })
+                    }
+                    _ => None,
+                }
+            }
+        })
+        .collect();
+    let modifies = if modified.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            #[cfg_attr(any(kani, feature = "debug_log"), kani::modifies(#(#modified),*))]
+        }
+    };
+    quote! {
+        #(#[cfg_attr(any(kani, feature = "debug_log"), kani::requires(#requires))])*
+        #(#[cfg_attr(any(kani, feature = "debug_log"), kani::ensures(#ensures))])*
+        #modifies
+    }
+}
+
+pub(crate) fn mentions_ident(tokens: TokenStream, name: &str) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => mentions_ident(group.stream(), name),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expr(text: &str) -> Expr {
+        syn::parse_str(text).unwrap()
+    }
+
+    #[test]
+    fn bind_self_renames_the_receiver_in_groups() {
+        let bound = bind_self(quote! { self.len < (self.cap + itself) });
+        assert_eq!(
+            bound.to_string(),
+            quote! { self_receiver.len < (self_receiver.cap + itself) }.to_string()
+        );
+    }
+
+    #[test]
+    fn requires_are_assumed_in_order() {
+        let assumed = assume_requires(&[expr("index < self.len"), expr("divisor != 0")]);
+        assert_eq!(
+            assumed.to_string(),
+            quote! {
+                kani::assume(index < self_receiver.len);
+                kani::assume(divisor != 0);
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn clause_text_is_tidy() {
+        let cases = [
+            (
+                "|ret| ret.is_some() == (index < self.len)",
+                "|ret| ret.is_some() == (index < self.len)",
+            ),
+            ("*ret <= dividend", "*ret <= dividend"),
+            ("a - -b", "a - -b"),
+            ("Self::is_valid", "Self::is_valid"),
+            ("v[i] + f(x, !y)", "v[i] + f(x, !y)"),
+            ("x.checked_add(1)?.max(&y)", "x.checked_add(1)?.max(&y)"),
+            ("|ret| !*ret || self.len > 0", "|ret| !*ret || self.len > 0"),
+            ("!!a && *&b != -*c", "!!a && *&b != -*c"),
+            ("a * *b & &c", "a * *b & &c"),
+        ];
+        for (clause, text) in cases {
+            assert_eq!(clause_text(&expr(clause)), text);
+        }
+    }
+
+    #[test]
+    fn mentions_ident_looks_into_groups() {
+        assert!(mentions_ident(quote! { f((result)) }, "result"));
+        assert!(!mentions_ident(quote! { results.len() }, "result"));
+    }
+
+    #[test]
+    fn assert_ensures_binds_result_only_when_used() {
+        let unused = assert_ensures(quote! { f() }, &[], quote!());
+        assert_eq!(unused.to_string(), quote! { let _ = f(); }.to_string());
+        let used = assert_ensures(quote! { f() }, &[], quote! { check(&result); });
+        assert_eq!(
+            used.to_string(),
+            quote! { let result = f(); check(&result); }.to_string()
+        );
+    }
+
+    #[test]
+    fn ensures_closures_take_a_reference_to_the_result() {
+        let asserted = assert_ensures(quote! { f() }, &[expr("|ret| !*ret || self.ok")], quote!());
+        assert_eq!(
+            asserted.to_string(),
+            quote! {
+                let result = f();
+                kani::assert(
+                    { let ret = &result; !*ret || self_receiver.ok },
+                    "autokani ensures: |ret| !*ret || self.ok"
+                );
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn replace_old_records_snapshots_in_order() {
+        let mut snapshots = Vec::new();
+        let replaced = replace_old(
+            quote! { self.len == old(self.len) + (old(v.len()) - v.old(1)) },
+            &mut snapshots,
+        );
+        assert_eq!(
+            replaced.to_string(),
+            quote! { self.len == old_0 + (old_1 - v.old(1)) }.to_string()
+        );
+        let exprs: Vec<String> = snapshots.iter().map(|(e, _)| e.to_string()).collect();
+        assert_eq!(
+            exprs,
+            [
+                quote! { self.len }.to_string(),
+                quote! { v.len() }.to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn replace_old_skips_paths_and_methods() {
+        let mut snapshots = Vec::new();
+        let tokens = quote! { module::old(x) && y.old(z) };
+        let replaced = replace_old(tokens.clone(), &mut snapshots);
+        assert_eq!(replaced.to_string(), tokens.to_string());
+        assert!(snapshots.is_empty());
+    }
+
+    #[test]
+    fn old_snapshots_are_cloned_before_the_call() {
+        let asserted = assert_ensures(
+            quote! { self_receiver.push(val) },
+            &[expr("self.len == old(self.len) + 1")],
+            quote!(),
+        );
+        assert_eq!(
+            asserted.to_string(),
+            quote! {
+                fn autokani_old_clone<T: ::core::clone::Clone>(value: &T) -> T {
+                    value.clone()
+                }
+                let old_0 = autokani_old_clone(&(self_receiver.len));
+                let result = self_receiver.push(val);
+                kani::assert(
+                    self_receiver.len == old_0 + 1,
+                    "autokani ensures: self.len == old(self.len) + 1"
+                );
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn contract_attrs_type_the_clauses_and_infer_modifies() {
+        let func: ItemFn =
+            syn::parse_str("fn f(&mut self, out: &mut u8, p: *mut u8, n: &u8) -> u8 { 0 }")
+                .unwrap();
+        let attrs = contract_attrs(
+            &func,
+            &[expr("n > 0")],
+            &[expr("|ret| *ret > 0"), expr("result == old(*out)")],
+        );
+        assert_eq!(
+            attrs.to_string(),
+            quote! {
+                #[cfg_attr(any(kani, feature = "debug_log"), kani::requires(n > 0))]
+                #[cfg_attr(any(kani, feature = "debug_log"), kani::ensures(|ret: &u8| *ret > 0))]
+                #[cfg_attr(
+                    any(kani, feature = "debug_log"),
+                    kani::ensures(|result: &u8| {
+                        let result = ::core::clone::Clone::clone(result);
+                        result == old(*out)
+                    })
+                )]
+                #[cfg_attr(any(kani, feature = "debug_log"), kani::modifies(self, out, p))]
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn invariant_checks() {
+        let pred: Expr = syn::parse_str("Self::is_valid").unwrap();
+        assert_eq!(
+            assert_invariant(Some(&pred), quote! { &result }).to_string(),
+            quote! {
+                kani::assert((Self::is_valid)(&result), "autokani preserves: Self::is_valid");
+            }
+            .to_string()
+        );
+        // without `preserves`, the `kani::Invariant` impl is checked if there is one
+        let check = assert_invariant(None, quote! { &*self_receiver }).to_string();
+        assert!(
+            check.contains(&quote! { impl<T: kani::Invariant> AutokaniCheckInvariant }.to_string())
+        );
+        assert!(check.contains(
+            &quote! { (&AutokaniInvariantProbe(&*self_receiver)).check_invariant(); }.to_string()
+        ));
+    }
+}
@@ -0,0 +1,606 @@
+//! Instantiation of generic target functions and impl blocks,
+//! e.g. `#[autokani_func(instantiate(T = u8))]`,
+//! and the bounds of the `Arbitrary` impls of generic types.
+//!
+//! Type parameters without an explicit argument are instantiated with a type chosen
+//! from their trait bounds, see [`default_argument`].
+use crate::contract::mentions_ident;
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::parse_quote;
+use syn::{
+    parse::Parse, punctuated::Punctuated, token::Comma, FnArg, GenericArgument, GenericParam,
+    Generics, Ident, Path, PathArguments, TraitBoundModifier, Type, TypeParam, TypeParamBound,
+    WherePredicate,
+};
+
+/// Name of the type generated for the parameters bounded by marker traits only.
+const DUMMY_TYPE: &str = "AutokaniDummy";
+/// Bounds satisfied by `i32`, which also satisfies the bounds below.
+const NUMERIC_BOUNDS: &[&str] = &[
+    "Add",
+    "Sub",
+    "Mul",
+    "Div",
+    "Rem",
+    "Neg",
+    "AddAssign",
+    "SubAssign",
+    "MulAssign",
+    "DivAssign",
+    "RemAssign",
+    "BitAnd",
+    "BitOr",
+    "BitXor",
+    "Not",
+    "Shl",
+    "Shr",
+    "Sum",
+    "Product",
+    "Num",
+    "PrimInt",
+    "Integer",
+    "Signed",
+    "Zero",
+    "One",
+    "Bounded",
+];
+/// Bounds satisfied by `u8`.
+const ORDERED_BOUNDS: &[&str] = &["PartialEq", "Eq", "PartialOrd", "Ord", "Hash", "Display"];
+/// Bounds satisfied by the generated dummy type.
+const MARKER_BOUNDS: &[&str] = &[
+    "Copy", "Clone", "Debug", "Default", "Send", "Sync", "Sized", "Unpin",
+];
+
+/// One concrete instantiation of the generic parameters of a function.
+#[derive(Default)]
+pub(crate) struct Instantiation {
+    /// Argument of each type or const parameter, in declaration order.
+    params: Vec<(Ident, GenericArgument)>,
+    /// Parameters whose argument was chosen from their bounds.
+    chosen: Vec<Ident>,
+    /// Number of leading parameters which belong to the impl block of a method,
+    /// and are not part of the turbofish.
+    outer: usize,
+}
+
+impl Instantiation {
+    /// This instantiation of the parameters of a method, following the instantiation
+    /// `outer` of the parameters of its impl block.
+    pub fn within(&self, outer: &Instantiation) -> Instantiation {
+        Instantiation {
+            params: outer.params.iter().chain(&self.params).cloned().collect(),
+            chosen: outer.chosen.iter().chain(&self.chosen).cloned().collect(),
+            outer: outer.params.len(),
+        }
+    }
+
+    /// Suffix of the harness name, e.g. `_u8` for `check_f_u8`.
+    pub fn suffix(&self) -> String {
+        let mut suffix = String::new();
+        for (_, arg) in &self.params {
+            suffix.push('_');
+            let arg_text = match arg.to_token_stream().to_string().as_str() {
+                DUMMY_TYPE => "dummy".to_string(),
+                "()" => "unit".to_string(),
+                arg_text => arg_text.to_lowercase(),
+            };
+            let mut last_sep = true;
+            for c in arg_text.chars() {
+                if c.is_alphanumeric() {
+                    suffix.push(c);
+                    last_sep = false;
+                } else if !last_sep {
+                    suffix.push('_');
+                    last_sep = true;
+                }
+            }
+            if suffix.ends_with('_') {
+                suffix.pop();
+            }
+        }
+        suffix
+    }
+
+    /// Turbofish of the call site, e.g. `::<u8, 4>`.
+    pub fn turbofish(&self) -> TokenStream {
+        if self.params.len() == self.outer {
+            return quote!();
+        }
+        let args = self.params[self.outer..].iter().map(|(_, arg)| arg);
+        quote! { ::<#(#args),*> }
+    }
+
+    /// Doc line of the harness recording the instantiation.
+    pub fn describe(&self) -> Option<String> {
+        if self.params.is_empty() {
+            return None;
+        }
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|(param, arg)| {
+                let origin = if self.chosen.contains(param) {
+                    " (chosen from its bounds)"
+                } else {
+                    ""
+                };
+                format!("`{} = {}`{}", param, arg.to_token_stream(), origin)
+            })
+            .collect();
+        Some(format!(" Instantiated with {}.", params.join(", ")))
+    }
+
+    /// Items declared at the start of the harness body: the dummy type if it is used,
+    /// and a deprecated constant per chosen argument, whose use reports the choice
+    /// as a compile-time warning pointing at the parameter.
+    pub fn items(&self, func_name: &Ident) -> TokenStream {
+        let mut items = TokenStream::new();
+        let uses_dummy = self.params.iter().any(|(_, arg)| {
+            matches!(arg, GenericArgument::Type(Type::Path(ty)) if ty.path.is_ident(DUMMY_TYPE))
+        });
+        if uses_dummy {
+            let dummy = format_ident!("{}", DUMMY_TYPE);
+            items.extend(quote! {
+                #[derive(Clone, Copy, Debug, Default)]
+                struct #dummy;
+                impl kani::Arbitrary for #dummy {
+                    // not `Self`, which `replace_self` rewrites in harnesses hoisted out of impls
+                    fn any() -> #dummy {
+                        #dummy
+                    }
+                }
+            });
+        }
+        for param in &self.chosen {
+            let arg = self
+                .params
+                .iter()
+                .find(|(name, _)| name == param)
+                .map(|(_, arg)| arg);
+            let note = format!(
+                "autokani: `{}` of `{}` is instantiated with `{}`, chosen from its bounds; \
+                 set it with `instantiate({} = ..)`",
+                param,
+                func_name,
+                arg.to_token_stream(),
+                param
+            );
+            let note_ident = format_ident!(
+                "autokani_note_{}",
+                param.to_string().to_lowercase(),
+                span = param.span()
+            );
+            items.extend(quote! {
+                #[deprecated(note = #note)]
+                #[allow(non_upper_case_globals)]
+                const #note_ident: () = ();
+                let _ = #note_ident;
+            });
+        }
+        items
+    }
+
+    /// Replace the generic parameters in a syntax node, e.g. `Vec<T>` to `Vec<u8>`.
+    pub fn substitute<T: Parse + ToTokens + Clone>(&self, node: &T) -> T {
+        if self.params.is_empty() {
+            return node.clone();
+        }
+        let tokens = self.substitute_tokens(node.to_token_stream());
+        syn::parse2(tokens).unwrap_or_else(|_| node.clone())
+    }
+
+    /// Replace the generic parameters in the types of `inputs`.
+    pub fn substitute_inputs(&self, inputs: &Punctuated<FnArg, Comma>) -> Punctuated<FnArg, Comma> {
+        inputs
+            .iter()
+            .map(|arg| match arg {
+                FnArg::Typed(pat_type) => {
+                    let mut pat_type = pat_type.clone();
+                    pat_type.ty = Box::new(self.substitute(&*pat_type.ty));
+                    FnArg::Typed(pat_type)
+                }
+                receiver => receiver.clone(),
+            })
+            .collect()
+    }
+
+    fn substitute_tokens(&self, tokens: TokenStream) -> TokenStream {
+        let mut substituted: Vec<TokenTree> = Vec::new();
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => {
+                    // `path::T` and `value.T` do not refer to the parameter
+                    let is_qualified = matches!(substituted.last(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':' || punct.as_char() == '.');
+                    match self.params.iter().find(|(param, _)| *param == ident) {
+                        Some((_, arg)) if !is_qualified => {
+                            substituted.extend(arg.to_token_stream());
+                        }
+                        _ => substituted.push(TokenTree::Ident(ident)),
+                    }
+                }
+                TokenTree::Group(group) => {
+                    let mut inner =
+                        Group::new(group.delimiter(), self.substitute_tokens(group.stream()));
+                    inner.set_span(group.span());
+                    substituted.push(TokenTree::Group(inner));
+                }
+                token => substituted.push(token),
+            }
+        }
+        substituted.into_iter().collect()
+    }
+}
+
+/// Replace `Self` by `self_ty` in the tokens of a harness generated outside of its impl block,
+/// `Self::item` becomes `Type::<T>::item`. In the impl of a trait, given with the names of the
+/// items it defines, `Self::item` becomes `<Type as Trait>::item` for these items.
+pub(crate) fn replace_self(
+    tokens: TokenStream,
+    self_ty: &Type,
+    trait_impl: Option<(&Path, &[Ident])>,
+) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut replaced: Vec<TokenTree> = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Ident(ident) if ident == "Self" => {
+                let is_path = matches!(tokens.get(i + 1), Some(TokenTree::Punct(punct)) if punct.as_char() == ':');
+                let item = match tokens.get(i + 3) {
+                    Some(TokenTree::Ident(item)) if is_path => Some(item),
+                    _ => None,
+                };
+                match (trait_impl, item) {
+                    (Some((trait_path, items)), Some(item)) if items.contains(item) => {
+                        replaced.extend(quote! { <#self_ty as #trait_path> });
+                    }
+                    _ if is_path => replaced.extend(expr_path(self_ty)),
+                    _ => replaced.extend(self_ty.to_token_stream()),
+                }
+            }
+            TokenTree::Group(group) => {
+                let mut inner = Group::new(
+                    group.delimiter(),
+                    replace_self(group.stream(), self_ty, trait_impl),
+                );
+                inner.set_span(group.span());
+                replaced.push(TokenTree::Group(inner));
+            }
+            token => replaced.push(token.clone()),
+        }
+    }
+    replaced.into_iter().collect()
+}
+
+/// `ty` as the prefix of a path expression, e.g. `Stack::<u8>`, which Kani also resolves
+/// in `proof_for_contract`. Types other than plain paths are written `<ty>`.
+fn expr_path(ty: &Type) -> TokenStream {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            let mut path = type_path.path.clone();
+            for segment in &mut path.segments {
+                if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+                    args.colon2_token = Some(Default::default());
+                }
+            }
+            path.to_token_stream()
+        }
+        _ => quote! { <#ty> },
+    }
+}
+
+/// The instantiations of `generics` requested by `instantiate(..)`: the cartesian product
+/// of the arguments given for each type and const parameter.
+/// A function without such parameters has a single, empty instantiation.
+///
+/// In `contract` mode, the target of the harness is named outside of its body, so marker-only
+/// bounds are instantiated with `()` instead of a dummy type declared in the body.
+pub(crate) fn instantiations(
+    generics: &Generics,
+    requested: &[(Ident, GenericArgument)],
+    contract: bool,
+) -> syn::Result<Vec<Instantiation>> {
+    let params: Vec<(&Ident, Option<&TypeParam>)> = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(type_param) => Some((&type_param.ident, Some(type_param))),
+            GenericParam::Const(const_param) => Some((&const_param.ident, None)),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    for (name, _) in requested {
+        if !params.iter().any(|(param, _)| *param == name) {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("no type or const parameter named `{}`", name),
+            ));
+        }
+    }
+    let mut instantiations = vec![Instantiation::default()];
+    for (param, type_param) in params {
+        let mut args: Vec<GenericArgument> = requested
+            .iter()
+            .filter(|(name, _)| name == param)
+            .map(|(_, arg)| arg.clone())
+            .collect();
+        let chosen = args.is_empty();
+        if chosen {
+            let type_param = type_param.ok_or_else(|| {
+                syn::Error::new(
+                    param.span(),
+                    format!(
+                        "const parameter `{}` needs an instantiation, e.g. `instantiate({} = 4)`",
+                        param, param
+                    ),
+                )
+            })?;
+            args.push(default_argument(generics, type_param, contract)?);
+        }
+        instantiations = instantiations
+            .into_iter()
+            .flat_map(|inst| {
+                args.iter().map(move |arg| {
+                    let mut params = inst.params.clone();
+                    params.push((param.clone(), arg.clone()));
+                    let mut chosen_params = inst.chosen.clone();
+                    if chosen {
+                        chosen_params.push(param.clone());
+                    }
+                    Instantiation {
+                        params,
+                        chosen: chosen_params,
+                        outer: 0,
+                    }
+                })
+            })
+            .collect();
+    }
+    Ok(instantiations)
+}
+
+/// The argument of a type parameter without `instantiate`, chosen from its trait bounds:
+/// `i32` for numeric bounds, `u8` for comparisons and a dummy type for marker traits only.
+fn default_argument(
+    generics: &Generics,
+    type_param: &TypeParam,
+    contract: bool,
+) -> syn::Result<GenericArgument> {
+    let param = &type_param.ident;
+    let where_bounds = generics
+        .where_clause
+        .iter()
+        .flat_map(|where_clause| &where_clause.predicates)
+        .filter_map(|predicate| match predicate {
+            WherePredicate::Type(predicate) => match &predicate.bounded_ty {
+                Type::Path(ty) if ty.qself.is_none() && ty.path.is_ident(param) => {
+                    Some(&predicate.bounds)
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .flatten();
+    let mut numeric = false;
+    let mut ordered = false;
+    for bound in type_param.bounds.iter().chain(where_bounds) {
+        let trait_bound = match bound {
+            TypeParamBound::Trait(trait_bound) => trait_bound,
+            TypeParamBound::Lifetime(_) => continue,
+        };
+        if let TraitBoundModifier::Maybe(_) = trait_bound.modifier {
+            continue;
+        }
+        let trait_name = match trait_bound.path.segments.last() {
+            Some(segment) => segment.ident.to_string(),
+            None => continue,
+        };
+        if NUMERIC_BOUNDS.contains(&trait_name.as_str()) {
+            numeric = true;
+        } else if ORDERED_BOUNDS.contains(&trait_name.as_str()) {
+            ordered = true;
+        } else if !MARKER_BOUNDS.contains(&trait_name.as_str()) {
+            return Err(syn::Error::new_spanned(
+                &trait_bound.path,
+                format!(
+                    "cannot choose a type for `{}` from the bound `{}`, \
+                     set it with `instantiate({} = ..)`",
+                    param,
+                    trait_bound.path.to_token_stream(),
+                    param
+                ),
+            ));
+        }
+    }
+    let ty = if numeric {
+        "i32"
+    } else if ordered {
+        "u8"
+    } else if contract {
+        "()"
+    } else {
+        DUMMY_TYPE
+    };
+    let ty: Type = syn::parse_str(ty)?;
+    Ok(GenericArgument::Type(ty))
+}
+
+/// `generics` with a `kani::Arbitrary` bound on each type parameter mentioned by `types`,
+/// for an `Arbitrary` impl which generates values of these types.
+/// `PhantomData` is generated without its parameter and needs no bound.
+pub(crate) fn with_arbitrary_bounds<'a>(
+    generics: &Generics,
+    types: impl IntoIterator<Item = &'a Type>,
+) -> Generics {
+    let types: Vec<&Type> = types
+        .into_iter()
+        .filter(|ty| match ty {
+            Type::Path(ty) => ty
+                .path
+                .segments
+                .last()
+                .is_none_or(|segment| segment.ident != "PhantomData"),
+            _ => true,
+        })
+        .collect();
+    let mut generics = generics.clone();
+    let bounded: Vec<Ident> = generics
+        .type_params()
+        .filter(|param| {
+            let name = param.ident.to_string();
+            types
+                .iter()
+                .any(|ty| mentions_ident(ty.to_token_stream(), &name))
+        })
+        .map(|param| param.ident.clone())
+        .collect();
+    if !bounded.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for param in bounded {
+            where_clause
+                .predicates
+                .push(parse_quote!(#param: kani::Arbitrary));
+        }
+    }
+    generics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generics(text: &str) -> Generics {
+        let func: syn::ItemFn = syn::parse_str(&format!("fn f{}() {{}}", text)).unwrap();
+        func.sig.generics
+    }
+
+    fn requested(text: &str) -> Vec<(Ident, GenericArgument)> {
+        let args: crate::attr::HarnessArgs =
+            syn::parse_str(&format!("instantiate({})", text)).unwrap();
+        args.instantiate
+    }
+
+    #[test]
+    fn method_instantiation_within_its_impl_block() {
+        let outer = &instantiations(&generics("<T>"), &requested("T = u16"), false).unwrap()[0];
+        let inner = &instantiations(&generics("<U>"), &requested("U = u8"), false).unwrap()[0];
+        let inst = inner.within(outer);
+        assert_eq!(inst.suffix(), "_u16_u8");
+        assert_eq!(inst.turbofish().to_string(), quote! { ::<u8> }.to_string());
+        let ty: Type = syn::parse_str("Vec<(T, U)>").unwrap();
+        assert_eq!(
+            inst.substitute(&ty).to_token_stream().to_string(),
+            quote! { Vec<(u16, u8)> }.to_string()
+        );
+    }
+
+    #[test]
+    fn replace_self_in_inherent_and_trait_impls() {
+        let tokens = quote! { let x: Self = Self::new(Self::CAP); Self::next(&mut x) };
+        let stack: Type = syn::parse_str("Stack<u8>").unwrap();
+        assert_eq!(
+            replace_self(tokens.clone(), &stack, None).to_string(),
+            quote! { let x: Stack<u8> = Stack::<u8>::new(Stack::<u8>::CAP); Stack::<u8>::next(&mut x) }
+                .to_string()
+        );
+        let trait_path: Path = syn::parse_str("Iterator").unwrap();
+        let items = [format_ident!("next")];
+        assert_eq!(
+            replace_self(tokens, &stack, Some((&trait_path, &items))).to_string(),
+            quote! {
+                let x: Stack<u8> = Stack::<u8>::new(Stack::<u8>::CAP);
+                <Stack<u8> as Iterator>::next(&mut x)
+            }
+            .to_string()
+        );
+        let slice: Type = syn::parse_str("[u8]").unwrap();
+        assert_eq!(
+            replace_self(quote! { Self::len(x) }, &slice, None).to_string(),
+            quote! { <[u8]>::len(x) }.to_string()
+        );
+    }
+
+    /// The harness name suffixes of the instantiations of `generics` given `requested`.
+    fn suffixes(generics_text: &str, requested_text: &str, contract: bool) -> Vec<String> {
+        instantiations(
+            &generics(generics_text),
+            &requested(requested_text),
+            contract,
+        )
+        .unwrap()
+        .iter()
+        .map(Instantiation::suffix)
+        .collect()
+    }
+
+    fn error(generics_text: &str, requested_text: &str) -> String {
+        match instantiations(&generics(generics_text), &requested(requested_text), false) {
+            Ok(_) => panic!("`{}` was instantiated", generics_text),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn cartesian_product_of_the_requested_arguments() {
+        assert_eq!(
+            suffixes(
+                "<T: Ord, U, const N: usize>",
+                "T = u8, T = i32, U = bool, U = char, N = 4",
+                false
+            ),
+            ["_u8_bool_4", "_u8_char_4", "_i32_bool_4", "_i32_char_4"]
+        );
+        assert_eq!(suffixes("<'a>", "", false), [""]);
+        let inst = &instantiations(
+            &generics("<'a, T, const N: usize>"),
+            &requested("T = Vec<u8>, N = 4"),
+            false,
+        )
+        .unwrap()[0];
+        assert_eq!(inst.suffix(), "_vec_u8_4");
+        assert_eq!(
+            inst.turbofish().to_string(),
+            quote! { ::<Vec<u8>, 4> }.to_string()
+        );
+    }
+
+    #[test]
+    fn arguments_which_cannot_be_chosen() {
+        assert_eq!(
+            error("<const N: usize>", ""),
+            "const parameter `N` needs an instantiation, e.g. `instantiate(N = 4)`"
+        );
+        assert_eq!(
+            error("<T>", "U = u8"),
+            "no type or const parameter named `U`"
+        );
+    }
+
+    #[test]
+    fn arguments_chosen_from_bounds() {
+        assert_eq!(suffixes("<T: Add<Output = T> + Copy>", "", false), ["_i32"]);
+        assert_eq!(suffixes("<T>() where T: Ord", "", false), ["_u8"]);
+        assert_eq!(suffixes("<T: Clone + ?Sized>", "", false), ["_dummy"]);
+        assert_eq!(suffixes("<T: Clone>", "", true), ["_unit"]);
+        assert_eq!(
+            error("<S: Store>", ""),
+            "cannot choose a type for `S` from the bound `Store`, set it with `instantiate(S = ..)`"
+        );
+    }
+
+    #[test]
+    fn chosen_arguments_are_reported() {
+        let inst =
+            &instantiations(&generics("<T: Ord, U>"), &requested("U = bool"), false).unwrap()[0];
+        assert_eq!(
+            inst.describe().unwrap(),
+            " Instantiated with `T = u8` (chosen from its bounds), `U = bool`."
+        );
+        let items = inst.items(&format_ident!("sort")).to_string();
+        assert!(items.contains(
+            "autokani: `T` of `sort` is instantiated with `u8`, chosen from its bounds; \
+             set it with `instantiate(T = ..)`"
+        ));
+        assert!(!items.contains("AutokaniDummy"));
+    }
+}
@@ -1,14 +1,47 @@
+mod attr;
+mod contract;
+mod generics;
+mod unwind;
+
+use attr::{
+    ArbitraryArgs, ArgOptions, FieldArgs, FieldSource, FloatMode, HarnessArgs, ImplArgs,
+    IntStrategy, StringMode, TraitArgs,
+};
+use contract::SELF_RECEIVER;
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
-    parse_macro_input, token::Mut, AngleBracketedGenericArguments, FnArg, Ident, ImplItem,
-    ImplItemMethod, Item, ItemImpl, ItemStruct, Pat, Path, Receiver, ReturnType, Type, TypeArray,
-    TypePath, TypePtr, TypeReference, TypeSlice, TypeTuple,
+    parse::Parser, parse_macro_input, token::Mut, AngleBracketedGenericArguments, Expr, FnArg,
+    Ident, ImplItem, ImplItemMethod, Item, ItemEnum, ItemImpl, ItemStruct, Pat, Path, Receiver,
+    ReturnType, TraitItem, Type, TypeArray, TypePath, TypePtr, TypeReference, TypeSlice, TypeTuple,
 };
 
 const ARR_LIMIT: usize = 16;
+const STRING_LIMIT: usize = 8;
+/// Default magnitude limit of integers, applied to the types wide enough to exceed it.
+const INT_LIMIT: u32 = 100000000;
+/// Integer types with their signedness and width in bits.
+const INT_TYPES: &[(&str, bool, u32)] = &[
+    ("u8", false, 8),
+    ("u16", false, 16),
+    ("u32", false, 32),
+    ("u64", false, 64),
+    ("u128", false, 128),
+    ("usize", false, 64),
+    ("i8", true, 8),
+    ("i16", true, 16),
+    ("i32", true, 32),
+    ("i64", true, 64),
+    ("i128", true, 128),
+    ("isize", true, 64),
+];
 trait ArbitraryInit {
-    fn init_for_type(&self, arg_name: &str, mutability: &Option<Mut>) -> proc_macro2::TokenStream;
+    fn init_for_type(
+        &self,
+        arg_name: &str,
+        mutability: &Option<Mut>,
+        opts: &ArgOptions,
+    ) -> proc_macro2::TokenStream;
 }
 
 fn error_msg(msg: &str) -> proc_macro2::TokenStream {
@@ -21,9 +54,9 @@ fn error_msg(msg: &str) -> proc_macro2::TokenStream {
 /// The harness name is `check_{function_name}`.
 ///
 /// # Example
-/// ```rust
-/// use autokani::kani_test;
-/// #[kani_test]
+/// ```rust,ignore
+/// use autokani::autokani_func;
+/// #[autokani_func]
 /// pub fn multi_param(a: i16, b: u8, c: f32, d: bool) {
 ///     let _ = a;
 ///     let x = b as f32 + c ;
@@ -35,30 +68,281 @@ fn error_msg(msg: &str) -> proc_macro2::TokenStream {
 /// The above code will generate a test harness for the `multi_param` function.
 /// Run the harness with `cargo kani --harness check_multi_param`.
 /// The harness could find the possible arithmetic overflow in the function.
-pub fn autokani_func(_attr: TokenStream, item: TokenStream) -> TokenStream {
+///
+/// # Arguments
+/// - `unwind = N`: the `#[kani::unwind]` bound of the harness. By default it is derived
+///   from the largest collection generated for the arguments (64 if there is none). It is
+///   at least 64 when the receiver or an argument of a user-defined type is generated by
+///   its own `Arbitrary` impl, as the size of its collections is unknown.
+/// - `unwind_margin = N`: unwindings added on top of the derived bound (default 1).
+/// - `len(arg = N, ..)`: length limit of the `Vec`s, slices and `String`s generated
+///   for `arg`, including nested ones such as `Option<Vec<T>>` (default 16, 8 for strings).
+/// - `string(arg = mode, ..)`: how the `String`/`&str` `arg` is generated. Its length always
+///   varies up to the limit; `mode` is one of `any` (default), `ascii`, `digit`, `hex`,
+///   `alpha`, `alnum`, a character class literal such as `"0-9a-f_"`, or `utf8` for
+///   arbitrary bytes that form valid UTF-8.
+/// - `range(arg = lo..hi, ..)`: the values of the integer `arg`, any range expression is
+///   accepted and `..` leaves it unconstrained. By default integers wider than 16 bits are
+///   limited to a magnitude below 100000000.
+/// - `integers = strategy`: how the integers without a `range` are constrained, including
+///   those inside tuples and `Option`s. `bounded` (default) applies the limit above, `full`
+///   leaves them unconstrained and `boundary` only allows 0, 1, -1, MIN, MAX, MAX - 1 and
+///   powers of two, for fast smoke checks.
+/// - `float(arg = mode, ..)` and `floats = mode`: the domain of the floats of `arg`, or of
+///   all floats of the harness. `mode` is one of `any` (default), `finite`, `not_nan`,
+///   `normal` or a range such as `-1e6..1e6`.
+/// - `requires = expr`: a precondition assumed once all the arguments are generated,
+///   written with the parameter names of the function (`self` for the receiver).
+///   May be given several times.
+/// - `ensures = clause`: a postcondition asserted after the call. The clause is either
+///   a closure over a reference to the return value, e.g. `|ret| ret.is_some()`, or an
+///   expression over `result`. It may also refer to the arguments, e.g. to `&mut`
+///   arguments after the call, and to their values before the call through `old(expr)`,
+///   which clones `expr` right before the call (`expr` must be `Clone`).
+///   May be given several times.
+/// - `preserves = pred`: the invariant of `Self` asserted after the call, as `pred(&value)`
+///   where `pred` is a path such as `Self::is_valid` or a closure over `&Self`. It is checked
+///   on the receiver of a `&mut self` method and on the return value of a `self` method
+///   returning `Self`. Without `preserves`, such methods re-check the `kani::Invariant` impl
+///   of `Self` if there is one, e.g. from `#[kani_arbitrary(invariant = .., impl_invariant)]`.
+/// - `instantiate(T = ty, N = value, ..)`: the arguments of the generic parameters of the
+///   function. A parameter may be given several arguments and one harness is generated
+///   per combination, named after them, e.g. `check_{function_name}_u8`. The clauses may
+///   refer to the parameters. Const parameters need an argument, while type parameters
+///   default to a type chosen from their bounds: `i32` for numeric traits, `u8` for
+///   comparisons and a dummy struct for marker traits only. The choice is reported
+///   as a compile-time warning.
+///
+/// ```rust,ignore
+/// use autokani::autokani_func;
+/// #[autokani_func(unwind = 8)]
+/// pub fn sum(v: Vec<u8>) -> u8 {
+///     v.iter().fold(0, |acc, x| acc.wrapping_add(*x))
+/// }
+///
+/// #[autokani_func(len(buf = 64, items = 3))]
+/// pub fn fill(buf: &mut [u8], items: Option<Vec<u8>>) {
+///     // ...
+/// }
+///
+/// // generates `check_largest_u8_4` and `check_largest_i32_4`
+/// #[autokani_func(instantiate(T = u8, T = i32, N = 4))]
+/// pub fn largest<T: Ord + Copy, const N: usize>(arr: [T; N]) -> T {
+///     // ...
+/// }
+///
+/// impl Array {
+///     /// # Safety
+///     /// `index` must be less than `self.len`.
+///     #[autokani_func(requires = index < self.len)]
+///     pub unsafe fn get_unchecked(&self, index: usize) -> i32 {
+///         *self.data.get_unchecked(index)
+///     }
+///
+///     #[autokani_func(ensures = |ret| ret.is_some() == (index < self.len))]
+///     pub fn get(&self, index: usize) -> Option<i32> {
+///         // ...
+///     }
+///
+///     #[autokani_func(ensures = self.len == old(self.len) + 1)]
+///     pub fn push(&mut self, val: i32) {
+///         // ...
+///     }
+/// }
+/// ```
+/// Unknown arguments are rejected with a compile error pointing at the key.
+pub fn autokani_func(attr: TokenStream, item: TokenStream) -> TokenStream {
+    harness_for_func(attr, item, CallStyle::Func, false)
+}
+
+#[proc_macro_attribute]
+/// `autokani_func` in contract mode: the `requires` and `ensures` clauses are attached to
+/// the target function as Kani function contracts, and the harness is a
+/// `#[kani::proof_for_contract]` which Kani uses to verify the contract.
+///
+/// `#[kani::modifies]` is inferred from the `&mut` and `*mut` parameters (and `&mut self`).
+/// Once verified, the contract can replace the function in other harnesses through
+/// `#[kani::stub_verified(function)]`. Run with `cargo kani -Z function-contracts`.
+///
+/// Accepts the same arguments as [`macro@autokani_func`], with the clauses written
+/// with the parameter names (`self` for the receiver). Note that an `ensures` clause
+/// over `result` clones the return value, the closure form does not.
+///
+/// Since a verified contract is trusted for every input satisfying `requires`, integers
+/// are unconstrained by default (`integers = full`): only the `range`, `integers` and
+/// `requires` given explicitly restrict the inputs. `preserves` is not accepted,
+/// the invariant is stated as an `ensures` clause instead.
+///
+/// # Example
+/// ```rust,ignore
+/// use autokani::autokani_contract;
+/// #[autokani_contract(requires = divisor != 0, ensures = |ret| *ret <= dividend)]
+/// pub fn div(dividend: u32, divisor: u32) -> u32 {
+///     dividend / divisor
+/// }
+/// ```
+pub fn autokani_contract(attr: TokenStream, item: TokenStream) -> TokenStream {
+    harness_for_func(attr, item, CallStyle::Func, true)
+}
+
+/// How a harness calls its target function.
+#[derive(Clone, Copy, PartialEq)]
+enum CallStyle {
+    /// `f(..)`, or `self.f(..)` for methods.
+    Func,
+    /// `Self::f(..)`, passing the receiver as the first argument.
+    Assoc,
+}
+
+fn harness_for_func(
+    attr: TokenStream,
+    item: TokenStream,
+    style: CallStyle,
+    contract: bool,
+) -> TokenStream {
+    let args = parse_macro_input!(attr as HarnessArgs);
     let input = parse_macro_input!(item as Item);
     let func = match input {
         Item::Fn(func) => func,
         _ => {
-            return error_msg("`kani_test` can only be used on functions.").into();
+            let name = match (style, contract) {
+                (CallStyle::Assoc, _) => "autokani_method",
+                (CallStyle::Func, true) => "autokani_contract",
+                (CallStyle::Func, false) => "autokani_func",
+            };
+            return error_msg(&format!("`{}` can only be used on functions.", name)).into();
         }
     };
 
+    if let Err(err) = check_harness_args(&args, &func.sig, contract) {
+        return err.to_compile_error().into();
+    }
+    let instantiations =
+        match generics::instantiations(&func.sig.generics, &args.instantiate, contract) {
+            Ok(instantiations) => instantiations,
+            Err(err) => return err.to_compile_error().into(),
+        };
+    let harnesses = instantiations.iter().map(|inst| {
+        let harness_name = quote::format_ident!("check_{}{}", func.sig.ident, inst.suffix());
+        harness_for_instantiation(&args, &func, inst, &harness_name, style, contract)
+    });
+    let contract_attrs = if contract {
+        contract::contract_attrs(&func, &args.requires, &args.ensures)
+    } else {
+        quote!()
+    };
+    let output = quote! {
+        #contract_attrs
+        #func
+
+        #(#harnesses)*
+    };
+    output.into()
+}
+
+/// Check that the arguments of a harness apply to the function with signature `sig`.
+fn check_harness_args(args: &HarnessArgs, sig: &syn::Signature, contract: bool) -> syn::Result<()> {
+    if let Some((arg, kind)) = unsupported_arg(sig) {
+        return Err(syn::Error::new_spanned(
+            arg,
+            format!(
+                "autokani cannot generate {} for the harness of `{}`",
+                kind, sig.ident
+            ),
+        ));
+    }
+    args.check_arg_names(&sig.inputs)?;
+    if let Some(preserves) = &args.preserves {
+        if contract {
+            return Err(syn::Error::new_spanned(
+                preserves,
+                "`preserves` is not supported by `autokani_contract`, state the invariant in `ensures`",
+            ));
+        }
+        if invariant_target(sig).is_none() {
+            return Err(syn::Error::new_spanned(
+                preserves,
+                "`preserves` needs a `&mut self` receiver, or a `self` receiver with `Self` returned",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The first argument of `sig` which a harness cannot generate, with what it is:
+/// a typed `self` receiver such as `self: Box<Self>`, or an argument whose type
+/// has no arbitrary value, such as `impl Trait`.
+fn unsupported_arg(sig: &syn::Signature) -> Option<(&FnArg, &'static str)> {
+    sig.inputs.iter().find_map(|arg| {
+        let pat_type = match arg {
+            FnArg::Typed(pat_type) => pat_type,
+            FnArg::Receiver(_) => return None,
+        };
+        if matches!(&*pat_type.pat, Pat::Ident(pat_ident) if pat_ident.ident == "self") {
+            return Some((arg, "a typed `self` receiver"));
+        }
+        let kind = match unsupported_type(&pat_type.ty)? {
+            Type::ImplTrait(_) => "an `impl Trait` argument",
+            Type::TraitObject(_) => "a `dyn Trait` argument",
+            Type::BareFn(_) => "a function pointer argument",
+            _ => "an argument of this type",
+        };
+        Some((arg, kind))
+    })
+}
+
+/// The part of `ty` which `init_for_type` cannot generate, if any.
+fn unsupported_type(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .iter()
+            .filter_map(|segment| match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => Some(&args.args),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => unsupported_type(ty),
+                syn::GenericArgument::Binding(binding) => unsupported_type(&binding.ty),
+                _ => None,
+            }),
+        Type::Array(type_arr) => unsupported_type(&type_arr.elem),
+        Type::Slice(type_slice) => unsupported_type(&type_slice.elem),
+        Type::Reference(type_ref) => unsupported_type(&type_ref.elem),
+        Type::Ptr(type_ptr) => unsupported_type(&type_ptr.elem),
+        Type::Tuple(type_tuple) => type_tuple.elems.iter().find_map(unsupported_type),
+        _ => Some(ty),
+    }
+}
+
+/// Generate the harness of `func` with its generic parameters instantiated by `inst`.
+fn harness_for_instantiation(
+    args: &HarnessArgs,
+    func: &syn::ItemFn,
+    inst: &generics::Instantiation,
+    harness_name: &Ident,
+    style: CallStyle,
+    contract: bool,
+) -> proc_macro2::TokenStream {
     let func_name = &func.sig.ident;
-    let inputs = &func.sig.inputs;
-    let harness_name = quote::format_ident!("check_{}", func_name);
+    let inputs = inst.substitute_inputs(&func.sig.inputs);
     let mut harness_body = Vec::new();
+    let mut receiver_arg = None;
     let mut call_args: Vec<proc_macro2::TokenStream> = Vec::new();
-    let mut call_path: Vec<proc_macro2::TokenStream> = Vec::new();
 
-    for arg in inputs {
+    for arg in &inputs {
         match arg {
             FnArg::Receiver(receiver) => {
-                let arg_placeholder = "self_receiver";
-                let init_stmt = receiver.init_for_type(arg_placeholder, &receiver.mutability);
+                let arg_placeholder = SELF_RECEIVER;
+                let init_stmt = receiver.init_for_type(
+                    arg_placeholder,
+                    &receiver.mutability,
+                    &ArgOptions::default(),
+                );
                 harness_body.push(init_stmt);
-                let receiver_ident = quote::format_ident!("{}", arg_placeholder);
-                call_path.push(quote! { #receiver_ident });
+                receiver_arg = Some(quote::format_ident!("{}", arg_placeholder));
             }
             FnArg::Typed(pat_type) => {
                 let arg_name = match &*pat_type.pat {
@@ -70,7 +354,13 @@ pub fn autokani_func(_attr: TokenStream, item: TokenStream) -> TokenStream {
                     Pat::Ident(pat_ident) => pat_ident.mutability,
                     _ => None,
                 };
-                let init_stmt = arg_type.init_for_type(&arg_name, &mutability);
+                let mut opts = args.arg_options(&arg_name);
+                // a contract is verified over its whole `requires` domain, so only the
+                // restrictions written by the user apply
+                if contract && args.integers.is_none() {
+                    opts.integers = IntStrategy::Full;
+                }
+                let init_stmt = arg_type.init_for_type(&arg_name, &mutability, &opts);
                 harness_body.push(init_stmt);
 
                 let arg_ident = quote::format_ident!("{}", arg_name);
@@ -78,38 +368,78 @@ pub fn autokani_func(_attr: TokenStream, item: TokenStream) -> TokenStream {
             }
         }
     }
-    call_path.push(quote! { #func_name });
+    let turbofish = inst.turbofish();
+    let call = match (style, &receiver_arg) {
+        (CallStyle::Func, Some(receiver)) => {
+            quote! { #receiver.#func_name #turbofish(#(#call_args),*) }
+        }
+        (CallStyle::Func, None) => quote! { #func_name #turbofish(#(#call_args),*) },
+        (CallStyle::Assoc, receiver) => {
+            let receiver = receiver.iter();
+            quote! { Self::#func_name #turbofish(#(#receiver,)* #(#call_args),*) }
+        }
+    };
+    let call = wrap_unsafe(func, call);
+    let unwind = unwind::unwind_attr(args, &inputs);
+    let inst_doc = inst.describe().map(|doc| quote! { #[doc = #doc] });
+    let inst_items = inst.items(func_name);
+
+    if contract {
+        let target = if receiver_arg.is_some() || style == CallStyle::Assoc {
+            quote! { Self::#func_name #turbofish }
+        } else {
+            quote! { #func_name #turbofish }
+        };
+        return quote! {
+            #[cfg(any(kani, feature = "debug_log"))]
+            #[kani::proof_for_contract(#target)]
+            /// Kani Harness Generated by autokani
+            #inst_doc
+            #unwind
+            pub fn #harness_name() {
+                #inst_items
+                #(#harness_body)*
+                let _ = #call;
+            }
+        };
+    }
 
-    let harness_code = quote! {
+    let requires: Vec<Expr> = args.requires.iter().map(|c| inst.substitute(c)).collect();
+    let ensures: Vec<Expr> = args.ensures.iter().map(|c| inst.substitute(c)).collect();
+    let requires = contract::assume_requires(&requires);
+    let invariant_check = match invariant_target(&func.sig) {
+        Some(target) => contract::assert_invariant(args.preserves.as_ref(), target),
+        None => quote!(),
+    };
+    let ensures = contract::assert_ensures(call, &ensures, invariant_check);
+    quote! {
         #[cfg(any(kani, feature = "debug_log"))]
         #[kani::proof]
-        #[kani::unwind(64)]
         /// Kani Harness Generated by autokani
+        #inst_doc
+        #unwind
         pub fn #harness_name() {
+            #inst_items
             #(#harness_body)*
-            let _ = #(#call_path).*(#(#call_args),*);
+            #requires
+            #ensures
         }
-    };
-    let output = quote! {
-        #func
-
-        #harness_code
-    };
-    output.into()
+    }
 }
+
 #[proc_macro_attribute]
 /// Automatedly generate Kani one test harness for target method.
 /// The harness name is `check_{method_name}`.
 ///
 /// # Example
-/// ```rust
-/// use autokani::kani_test;
+/// ```rust,ignore
+/// use autokani::autokani_method;
 /// struct Obj {
 ///     a: u8,
 /// }
 /// impl Obj {
-/// #[kani_test]
-/// pub fn multi_param(&self, b: u8, c: f32, d: bool) {
+/// #[autokani_method]
+/// pub fn multi_param(&mut self, b: u8, c: f32, d: bool) {
 ///     let x = b as f32 + c ;
 ///     if d {
 ///         self.a = b + c as u8;
@@ -120,64 +450,500 @@ pub fn autokani_func(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// The above code will generate a test harness for the `multi_param` function.
 /// Run the harness with `cargo kani --harness check_multi_param`.
 /// The harness could find the possible arithmetic overflow in the function.
-pub fn autokani_method(_attr: TokenStream, item: TokenStream) -> TokenStream {
+///
+/// Accepts the same arguments as [`macro@autokani_func`].
+pub fn autokani_method(attr: TokenStream, item: TokenStream) -> TokenStream {
+    harness_for_func(attr, item, CallStyle::Assoc, false)
+}
+
+/// Attributes of the methods of an `autokani_impl` block which give the arguments of their harness.
+const HARNESS_ATTRS: &[&str] = &["autokani_func", "autokani_method", "autokani_contract"];
+
+#[proc_macro_attribute]
+/// Automatedly generate Kani test harnesses for the methods of an impl block.
+/// The harnesses are placed in a module next to the impl block, named after the type,
+/// e.g. `autokani_array::check_push`, as harnesses cannot be declared inside an impl block.
+///
+/// Both the methods with a receiver and the associated functions get a harness,
+/// called as `Type::method(receiver, args)` with arbitrary values, or as
+/// `<Type as Trait>::method(receiver, args)` in the impl of a trait such as `Iterator`,
+/// `Read` or `Add`. The module of a trait impl is named after both, e.g.
+/// `autokani_array_index_usize` for `impl Index<usize> for Array`. The receiver is generated
+/// through the `kani::Arbitrary` impl of the type, e.g. from `kani_arbitrary` or `extend_arbitrary`.
+/// Private methods are covered too, since the module is a child of the one of the impl block.
+/// A method whose arguments cannot be generated, such as a typed `self: Box<Self>` receiver
+/// or an `impl Trait` argument, is rejected with an error: leave it out with `exclude`.
+///
+/// # Arguments
+/// - `include = [push, pop]`: the methods which get a harness, all of them by default.
+/// - `exclude = [clear]`: the methods which get no harness.
+/// - `instantiate(T = ty, ..)`: the arguments of the generic parameters of the impl block,
+///   see [`macro@autokani_func`]. One set of harnesses is generated per combination.
+/// - `module = name`: the name of the module of the harnesses. The default one is built
+///   from the type, its generic arguments and the trait, so it must be set when a module
+///   holds several impl blocks of the same type, e.g. two `impl Array` blocks.
+///
+/// A method may carry `#[autokani_func(..)]` or `#[autokani_method(..)]` to set the arguments
+/// of its harness, which it then gets unless `exclude`d. With `#[autokani_contract(..)]`,
+/// the contract is attached to the method and its `proof_for_contract` harness is placed
+/// in the module as well, targeting `Type::method` or `<Type as Trait>::method`.
+///
+/// # Example
+/// ```rust,ignore
+/// use autokani::autokani_impl;
+/// #[autokani_impl(exclude = [clear])]
+/// impl Array {
+///     pub fn new(cap: usize) -> Self {
+///         // ...
+///     }
+///
+///     #[autokani_func(ensures = |ret| ret.is_some() == (index < self.len))]
+///     pub fn get(&self, index: usize) -> Option<i32> {
+///         // ...
+///     }
+///
+///     pub fn clear(&mut self) {
+///         // ...
+///     }
+/// }
+/// ```
+/// The above code generates `autokani_array::check_new` and `autokani_array::check_get`.
+///
+/// ```rust,ignore
+/// // generates `autokani_counter_iterator::check_next`
+/// #[autokani_impl]
+/// impl Iterator for Counter {
+///     type Item = u32;
+///
+///     #[autokani_func(ensures = |ret: &Option<u32>| ret.is_none() || self.count > 0)]
+///     fn next(&mut self) -> Option<u32> {
+///         // ...
+///     }
+/// }
+/// ```
+pub fn autokani_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as ImplArgs);
     let input = parse_macro_input!(item as Item);
-    let func = match input {
-        Item::Fn(func) => func,
+    let mut impl_block = match input {
+        Item::Impl(impl_block) => impl_block,
         _ => {
-            return error_msg("`kani_test` can only be used on functions.").into();
+            return error_msg("`autokani_impl` can only be used on impl blocks.").into();
         }
     };
+    let mut errors = Vec::new();
+    let methods: Vec<_> = impl_block
+        .items
+        .iter_mut()
+        .filter_map(|item| match item {
+            ImplItem::Method(method) => {
+                let harness_attr = take_harness_attr(&mut method.attrs);
+                let item = (method.clone(), harness_attr.clone());
+                if let Some(attr) = harness_attr.filter(is_contract_attr) {
+                    if let Err(err) = attach_contract(method, &attr) {
+                        errors.push(err.to_compile_error());
+                    }
+                }
+                Some(item)
+            }
+            _ => None,
+        })
+        .collect();
+    let trait_items = impl_block
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Method(method) => Some(method.sig.ident.clone()),
+            ImplItem::Const(item) => Some(item.ident.clone()),
+            ImplItem::Type(item) => Some(item.ident.clone()),
+            _ => None,
+        })
+        .collect();
+    let owner = MethodOwner {
+        generics: &impl_block.generics,
+        self_ty: &impl_block.self_ty,
+        trait_path: impl_block.trait_.as_ref().map(|(_, path, _)| path.clone()),
+        trait_items,
+        harness_prefix: String::new(),
+        harness_suffix: String::new(),
+    };
+    let harnesses = check_method_names(&args, &methods, "in this impl block")
+        .and_then(|_| harnesses_for_methods(&owner, &methods, &args))
+        .unwrap_or_else(|err| vec![err.to_compile_error()]);
+    let module = args
+        .module
+        .clone()
+        .unwrap_or_else(|| harness_module_name(&impl_block));
+    let output = quote! {
+        #impl_block
+        #(#errors)*
 
-    let func_name = &func.sig.ident;
-    let inputs = &func.sig.inputs;
-    let harness_name = quote::format_ident!("check_{}", func_name);
-    let mut harness_body = Vec::new();
-    let mut call_args: Vec<proc_macro2::TokenStream> = Vec::new();
+        #[cfg(any(kani, feature = "debug_log"))]
+        mod #module {
+            use super::*;
+            #(#harnesses)*
+        }
+    };
+    output.into()
+}
 
-    for arg in inputs {
-        match arg {
-            FnArg::Receiver(receiver) => {
-                let arg_placeholder = "self_receiver";
-                let init_stmt = receiver.init_for_type(arg_placeholder, &receiver.mutability);
-                harness_body.push(init_stmt);
-                let receiver_ident = quote::format_ident!("{}", arg_placeholder);
-                call_args.push(quote! { #receiver_ident });
-            }
-            FnArg::Typed(pat_type) => {
-                let arg_name = match &*pat_type.pat {
-                    syn::Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
-                    _ => "arg".to_string(),
-                };
-                let arg_type = &pat_type.ty;
-                let mutability = match &*pat_type.pat {
-                    Pat::Ident(pat_ident) => pat_ident.mutability,
-                    _ => None,
+#[proc_macro_attribute]
+/// Automatedly generate Kani test harnesses for the default methods of a trait,
+/// one per default method per implementor listed in `impls`.
+/// The harness name is `check_{Trait}_{method}_{Impl}`.
+///
+/// The default method is called as `<Impl as Trait>::method(receiver, args)` with arbitrary
+/// values, the receiver being generated through the `kani::Arbitrary` impl of the implementor,
+/// e.g. from `kani_arbitrary` or `extend_arbitrary`.
+///
+/// # Arguments
+/// - `impls = [VecStore, FileStore]`: the implementors, required.
+/// - `include`, `exclude` and `instantiate`: the default methods which get a harness and
+///   the arguments of the generic parameters of the trait, see [`macro@autokani_impl`].
+///
+/// As in `autokani_impl`, a default method may carry `#[autokani_func(..)]` to set the
+/// arguments of its harness.
+///
+/// # Example
+/// ```rust,ignore
+/// use autokani::autokani_trait;
+/// #[autokani_trait(impls = [VecStore, FileStore])]
+/// pub trait Store {
+///     fn get(&self, key: u8) -> Option<u32>;
+///
+///     #[autokani_func(ensures = |ret| *ret == self.get(key).is_some())]
+///     fn contains(&self, key: u8) -> bool {
+///         self.get(key).is_some()
+///     }
+/// }
+/// ```
+/// The above code generates `check_Store_contains_VecStore` and `check_Store_contains_FileStore`.
+pub fn autokani_trait(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as TraitArgs);
+    let input = parse_macro_input!(item as Item);
+    let mut item_trait = match input {
+        Item::Trait(item_trait) => item_trait,
+        _ => {
+            return error_msg("`autokani_trait` can only be used on traits.").into();
+        }
+    };
+    let mut errors = Vec::new();
+    let methods: Vec<_> = item_trait
+        .items
+        .iter_mut()
+        .filter_map(|item| match item {
+            TraitItem::Method(method) => {
+                let harness_attr = take_harness_attr(&mut method.attrs);
+                if let Some(attr) = harness_attr.as_ref().filter(|attr| is_contract_attr(attr)) {
+                    errors.push(
+                        syn::Error::new_spanned(
+                            attr,
+                            "`autokani_contract` is not supported on the methods of `autokani_trait`",
+                        )
+                        .to_compile_error(),
+                    );
+                    return None;
+                }
+                let method = ImplItemMethod {
+                    attrs: method.attrs.clone(),
+                    vis: syn::Visibility::Inherited,
+                    defaultness: None,
+                    sig: method.sig.clone(),
+                    block: method.default.clone()?,
                 };
-                let init_stmt = arg_type.init_for_type(&arg_name, &mutability);
-                harness_body.push(init_stmt);
-                let arg_ident = quote::format_ident!("{}", arg_name);
-                call_args.push(quote! { #arg_ident });
+                Some((method, harness_attr))
             }
+            _ => None,
+        })
+        .collect();
+    let trait_items: Vec<Ident> = item_trait
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Method(method) => Some(method.sig.ident.clone()),
+            TraitItem::Const(item) => Some(item.ident.clone()),
+            TraitItem::Type(item) => Some(item.ident.clone()),
+            _ => None,
+        })
+        .collect();
+    let trait_name = &item_trait.ident;
+    let (_, ty_generics, _) = item_trait.generics.split_for_impl();
+    let trait_path: Path = syn::parse_quote!(#trait_name #ty_generics);
+    let harnesses =
+        check_method_names(&args.methods, &methods, "with a default body in this trait")
+            .and_then(|_| {
+                let mut harnesses = Vec::new();
+                for self_ty in &args.impls {
+                    let owner = MethodOwner {
+                        generics: &item_trait.generics,
+                        self_ty,
+                        trait_path: Some(trait_path.clone()),
+                        trait_items: trait_items.clone(),
+                        harness_prefix: format!("{}_", trait_name),
+                        harness_suffix: format!("_{}", type_name_part(self_ty)),
+                    };
+                    harnesses.extend(harnesses_for_methods(&owner, &methods, &args.methods)?);
+                }
+                Ok(harnesses)
+            })
+            .unwrap_or_else(|err| vec![err.to_compile_error()]);
+    let output = quote! {
+        #item_trait
+        #(#errors)*
+
+        #(
+            #[allow(non_snake_case)]
+            #harnesses
+        )*
+    };
+    output.into()
+}
+
+/// `ty` as a part of a harness name, e.g. `Vec_u8` for `Vec<u8>`.
+fn type_name_part(ty: &Type) -> String {
+    let mut part = String::new();
+    for c in quote!(#ty).to_string().chars() {
+        if c.is_alphanumeric() {
+            part.push(c);
+        } else if !part.is_empty() && !part.ends_with('_') {
+            part.push('_');
         }
     }
-    let output = quote! {
-        #func
+    part.trim_end_matches('_').to_string()
+}
 
-        #[cfg(any(kani, feature = "debug_log"))]
-        #[kani::proof]
-        #[kani::unwind(64)]
-        /// Kani Harness Generated by autokani
-        pub fn #harness_name() {
-            #(#harness_body)*
-            let _ = Self::#func_name(#(#call_args),*);
+/// Remove the harness attribute from the `attrs` of a method, if it carries one.
+fn take_harness_attr(attrs: &mut Vec<syn::Attribute>) -> Option<syn::Attribute> {
+    let position = attrs.iter().position(|attr| {
+        attr.path
+            .segments
+            .last()
+            .is_some_and(|segment| HARNESS_ATTRS.contains(&segment.ident.to_string().as_str()))
+    })?;
+    Some(attrs.remove(position))
+}
+
+/// Whether `attr` is `#[autokani_contract(..)]`.
+fn is_contract_attr(attr: &syn::Attribute) -> bool {
+    attr.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "autokani_contract")
+}
+
+/// The arguments of a harness attribute, the default ones for a missing or bare attribute.
+fn harness_attr_args(attr: Option<&syn::Attribute>) -> syn::Result<HarnessArgs> {
+    match attr {
+        Some(attr) if !attr.tokens.is_empty() => attr.parse_args(),
+        _ => Ok(HarnessArgs::default()),
+    }
+}
+
+/// `method` as a free function, the form the harness generation expects.
+fn method_fn(method: &ImplItemMethod) -> syn::ItemFn {
+    syn::ItemFn {
+        attrs: method.attrs.clone(),
+        vis: method.vis.clone(),
+        sig: method.sig.clone(),
+        block: Box::new(method.block.clone()),
+    }
+}
+
+/// Attach the Kani contract of the `autokani_contract` attribute `attr` to `method`,
+/// whose `proof_for_contract` harness is generated outside of the impl block.
+fn attach_contract(method: &mut ImplItemMethod, attr: &syn::Attribute) -> syn::Result<()> {
+    let args = harness_attr_args(Some(attr))?;
+    let contract_attrs =
+        contract::contract_attrs(&method_fn(method), &args.requires, &args.ensures);
+    let contract_attrs = syn::Attribute::parse_outer.parse2(contract_attrs)?;
+    method.attrs.splice(0..0, contract_attrs);
+    Ok(())
+}
+
+/// Check that the methods named by `include` and `exclude` are among `methods`.
+fn check_method_names(
+    args: &ImplArgs,
+    methods: &[(ImplItemMethod, Option<syn::Attribute>)],
+    location: &str,
+) -> syn::Result<()> {
+    for name in args.include.iter().chain(&args.exclude) {
+        if !methods.iter().any(|(method, _)| method.sig.ident == *name) {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("no method named `{}` {}", name, location),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The type whose methods get harnesses outside of their impl block or trait.
+struct MethodOwner<'a> {
+    /// Generic parameters of the impl block or trait.
+    generics: &'a syn::Generics,
+    self_ty: &'a Type,
+    /// The implemented trait, if any.
+    trait_path: Option<Path>,
+    /// Items of the trait, `Self::item` is qualified with the trait for them.
+    trait_items: Vec<Ident>,
+    /// Text before the method name in the harness names, e.g. `Store_`.
+    harness_prefix: String,
+    /// Text after the method name in the harness names, e.g. `_VecStore`.
+    harness_suffix: String,
+}
+
+/// The harnesses of the selected `methods` of `owner`, one per instantiation
+/// of the generic parameters of the impl block or trait and of the method.
+fn harnesses_for_methods(
+    owner: &MethodOwner,
+    methods: &[(ImplItemMethod, Option<syn::Attribute>)],
+    args: &ImplArgs,
+) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    let mut harnesses = Vec::new();
+    for (method, harness_attr) in methods {
+        let name = &method.sig.ident;
+        let selected =
+            args.include.is_empty() || args.include.contains(name) || harness_attr.is_some();
+        if !selected || args.exclude.contains(name) {
+            continue;
+        }
+        if let Some((arg, kind)) = unsupported_arg(&method.sig) {
+            return Err(syn::Error::new_spanned(
+                arg,
+                format!(
+                    "autokani cannot generate {} for the harness of `{}`, \
+                     leave the method out with `exclude = [{}]`",
+                    kind, name, name
+                ),
+            ));
         }
+        let contract = harness_attr.as_ref().is_some_and(is_contract_attr);
+        let harness_args = harness_attr_args(harness_attr.as_ref())?;
+        check_harness_args(&harness_args, &method.sig, contract)?;
+        let func = method_fn(method);
+        // in contract mode the target is named outside of the harness body
+        let outer_instantiations =
+            generics::instantiations(owner.generics, &args.instantiate, contract)?;
+        for outer in &outer_instantiations {
+            let func = outer.substitute(&func);
+            let self_ty = outer.substitute(owner.self_ty);
+            let trait_path = owner
+                .trait_path
+                .as_ref()
+                .map(|trait_path| outer.substitute(trait_path));
+            let trait_impl = trait_path
+                .as_ref()
+                .map(|trait_path| (trait_path, owner.trait_items.as_slice()));
+            let instantiations =
+                generics::instantiations(&func.sig.generics, &harness_args.instantiate, contract)?;
+            for inst in instantiations {
+                let inst = inst.within(outer);
+                let harness_name = quote::format_ident!(
+                    "check_{}{}{}{}",
+                    owner.harness_prefix,
+                    name,
+                    owner.harness_suffix,
+                    inst.suffix()
+                );
+                let harness = harness_for_instantiation(
+                    &harness_args,
+                    &func,
+                    &inst,
+                    &harness_name,
+                    CallStyle::Assoc,
+                    contract,
+                );
+                harnesses.push(generics::replace_self(harness, &self_ty, trait_impl));
+            }
+        }
+    }
+    Ok(harnesses)
+}
+
+/// Name of the module holding the harnesses of `impl_block`, e.g. `autokani_array`,
+/// `autokani_stack_u8` for `impl Stack<u8>` or `autokani_array_index_usize`
+/// for `impl Index<usize> for Array`.
+fn harness_module_name(impl_block: &ItemImpl) -> Ident {
+    let self_ty = &impl_block.self_ty;
+    let type_name = match &**self_ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| quote!(#segment).to_string()),
+        _ => Some(quote!(#self_ty).to_string()),
     };
-    output.into()
+    let trait_name = impl_block
+        .trait_
+        .as_ref()
+        .and_then(|(_, trait_path, _)| trait_path.segments.last())
+        .map(|segment| quote!(#segment).to_string());
+    let mut name = String::from("autokani");
+    for part in [type_name, trait_name].into_iter().flatten() {
+        let mut prev_lower = false;
+        let mut last_sep = true;
+        name.push('_');
+        for c in part.chars() {
+            if !c.is_alphanumeric() {
+                if !last_sep {
+                    name.push('_');
+                    last_sep = true;
+                }
+                prev_lower = false;
+                continue;
+            }
+            if c.is_uppercase() && prev_lower {
+                name.push('_');
+            }
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+            last_sep = false;
+            name.extend(c.to_lowercase());
+        }
+        if name.ends_with('_') {
+            name.pop();
+        }
+    }
+    if name == "autokani" {
+        name.push_str("_impl");
+    }
+    quote::format_ident!("{}", name)
+}
+
+/// The value whose invariant is re-checked after a call to a method with signature `sig`:
+/// the receiver if it is `&mut self`, the return value if `self` is consumed and `Self` returned.
+fn invariant_target(sig: &syn::Signature) -> Option<proc_macro2::TokenStream> {
+    let receiver = match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) => receiver,
+        _ => return None,
+    };
+    let receiver_ident = quote::format_ident!("{}", SELF_RECEIVER);
+    match (&receiver.reference, &receiver.mutability) {
+        (Some(_), Some(_)) => Some(quote! { &*#receiver_ident }),
+        (Some(_), None) => None,
+        (None, _) => match &sig.output {
+            ReturnType::Type(_, ty) if matches!(&**ty, Type::Path(ty) if ty.path.is_ident("Self")) => {
+                Some(quote! { &result })
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Call an `unsafe fn` in an `unsafe` block,
+/// its safety preconditions are expected to be encoded by `requires`.
+fn wrap_unsafe(func: &syn::ItemFn, call: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    match func.sig.unsafety {
+        Some(_) => quote! { unsafe { #call } },
+        None => call,
+    }
 }
 
 impl ArbitraryInit for Receiver {
-    fn init_for_type(&self, arg_name: &str, mutability: &Option<Mut>) -> proc_macro2::TokenStream {
+    fn init_for_type(
+        &self,
+        arg_name: &str,
+        mutability: &Option<Mut>,
+        _opts: &ArgOptions,
+    ) -> proc_macro2::TokenStream {
         let arg_ident = quote::format_ident!("{}", arg_name);
         let to_ref = match self.reference {
             Some(_) => quote! { let #arg_ident = &#mutability #arg_ident; },
@@ -192,21 +958,20 @@ impl ArbitraryInit for Receiver {
 }
 
 impl ArbitraryInit for TypePath {
-    fn init_for_type(&self, arg_name: &str, mutability: &Option<Mut>) -> proc_macro2::TokenStream {
+    fn init_for_type(
+        &self,
+        arg_name: &str,
+        mutability: &Option<Mut>,
+        opts: &ArgOptions,
+    ) -> proc_macro2::TokenStream {
         // TODO: support Enum types
         let arg_ident = quote::format_ident!("{}", arg_name);
-        if self.path.is_ident("u32") || self.path.is_ident("u64") || self.path.is_ident("usize") {
-            quote! {
-                let #mutability #arg_ident = kani::any();
-                kani::assume(#arg_ident < 100000000);
-            }
-        } else if self.path.is_ident("i32") || self.path.is_ident("i64") {
-            quote! {
-                let #mutability #arg_ident = kani::any();
-                kani::assume(#arg_ident < 100000000 && #arg_ident > -100000000);
-            }
+        if let Some(&(_, signed, bits)) = INT_TYPES.iter().find(|(ty, ..)| self.path.is_ident(ty)) {
+            init_for_int(self, arg_name, mutability, opts, signed, bits)
+        } else if self.path.is_ident("f32") || self.path.is_ident("f64") {
+            init_for_float(self, arg_name, mutability, opts)
         } else if self.path.is_ident("String") || self.path.is_ident("str") {
-            init_for_string(arg_name, mutability)
+            init_for_string(arg_name, mutability, opts)
         } else if self.path.segments.last().is_some() {
             let final_seg = self.path.segments.last().unwrap();
             let inner_type = &final_seg.arguments;
@@ -216,8 +981,9 @@ impl ArbitraryInit for TypePath {
                 match vec_type {
                     syn::PathArguments::AngleBracketed(args) => {
                         if let Some(syn::GenericArgument::Type(ty)) = args.args.first() {
+                            let arr_limit = opts.len.unwrap_or(ARR_LIMIT);
                             quote! {
-                                let #mutability #arg_ident = kani::vec::any_vec::<#ty, #ARR_LIMIT>();
+                                let #mutability #arg_ident = kani::vec::any_vec::<#ty, #arr_limit>();
                             }
                         } else {
                             error_msg("Unsupported Vec Type")
@@ -230,7 +996,7 @@ impl ArbitraryInit for TypePath {
                 match option_type {
                     syn::PathArguments::AngleBracketed(args) => {
                         if let Some(syn::GenericArgument::Type(ty)) = args.args.first() {
-                            let init_stmt = ty.init_for_type(arg_name, mutability);
+                            let init_stmt = ty.init_for_type(arg_name, mutability, opts);
                             quote! {
                                 let #mutability #arg_ident = if kani::any::<bool>() {
                                     #init_stmt
@@ -254,13 +1020,13 @@ impl ArbitraryInit for TypePath {
                     }) => {
                         let ok_init = match args.first() {
                             Some(syn::GenericArgument::Type(ty)) => {
-                                ty.init_for_type(arg_name, mutability)
+                                ty.init_for_type(arg_name, mutability, opts)
                             }
                             _ => error_msg("Unsupported Result Type"),
                         };
                         let err_init = match args.last() {
                             Some(syn::GenericArgument::Type(ty)) => {
-                                ty.init_for_type(arg_name, mutability)
+                                ty.init_for_type(arg_name, mutability, opts)
                             }
                             _ => error_msg("Unsupported Result Type"),
                         };
@@ -276,11 +1042,15 @@ impl ArbitraryInit for TypePath {
                     }
                     _ => error_msg("Unsupported Result Pattern"),
                 }
+            } else if final_seg.ident == "PhantomData" {
+                quote! {
+                    let #mutability #arg_ident: #self = ::core::marker::PhantomData;
+                }
             } else {
-                // both typical types and user-defined structs are handled here
-                let final_ident = &final_seg.ident;
+                // both typical types and user-defined structs are handled here,
+                // keeping the generic arguments, e.g. `Pair<u8>`
                 quote! {
-                    let #mutability #arg_ident: #final_ident = kani::any();
+                    let #mutability #arg_ident: #self = kani::any();
                 }
             }
         } else {
@@ -289,18 +1059,141 @@ impl ArbitraryInit for TypePath {
     }
 }
 
-fn init_for_string(arg_name: &str, mutability: &Option<Mut>) -> proc_macro2::TokenStream {
-    const STRING_LIMIT: usize = 8;
+fn init_for_int(
+    int_type: &TypePath,
+    arg_name: &str,
+    mutability: &Option<Mut>,
+    opts: &ArgOptions,
+    signed: bool,
+    bits: u32,
+) -> proc_macro2::TokenStream {
+    let arg_ident = quote::format_ident!("{}", arg_name);
+    let int_limit = proc_macro2::Literal::u32_unsuffixed(INT_LIMIT);
+    let assumption = match (&opts.range, opts.integers) {
+        (Some(range), _) if range.from.is_none() && range.to.is_none() => quote!(),
+        (Some(range), _) => quote! {
+            kani::assume((#range).contains(&#arg_ident));
+        },
+        (None, IntStrategy::Full) => quote!(),
+        // types of 16 bits or less never exceed the default limit
+        (None, IntStrategy::Bounded) if bits <= 16 => quote!(),
+        (None, IntStrategy::Bounded) if signed => quote! {
+            kani::assume(#arg_ident < #int_limit && #arg_ident > -#int_limit);
+        },
+        (None, IntStrategy::Bounded) => quote! {
+            kani::assume(#arg_ident < #int_limit);
+        },
+        // `count_ones() == 1` holds for the powers of two, and for `MIN` of signed types
+        (None, IntStrategy::Boundary) if signed => quote! {
+            kani::assume(
+                #arg_ident == 0
+                    || #arg_ident == -1
+                    || #arg_ident.count_ones() == 1
+                    || #arg_ident == #int_type::MAX
+                    || #arg_ident == #int_type::MAX - 1
+            );
+        },
+        (None, IntStrategy::Boundary) => quote! {
+            kani::assume(
+                #arg_ident == 0
+                    || #arg_ident.count_ones() == 1
+                    || #arg_ident == #int_type::MAX
+                    || #arg_ident == #int_type::MAX - 1
+            );
+        },
+    };
+    quote! {
+        let #mutability #arg_ident: #int_type = kani::any();
+        #assumption
+    }
+}
+
+fn init_for_float(
+    float_type: &TypePath,
+    arg_name: &str,
+    mutability: &Option<Mut>,
+    opts: &ArgOptions,
+) -> proc_macro2::TokenStream {
+    let arg_ident = quote::format_ident!("{}", arg_name);
+    let assumption = match &opts.float {
+        FloatMode::Any => quote!(),
+        FloatMode::Finite => quote! { kani::assume(#arg_ident.is_finite()); },
+        FloatMode::NotNan => quote! { kani::assume(!#arg_ident.is_nan()); },
+        FloatMode::Normal => quote! { kani::assume(#arg_ident.is_normal()); },
+        FloatMode::Range(range) => quote! {
+            kani::assume((#range).contains(&#arg_ident));
+        },
+    };
+    quote! {
+        let #mutability #arg_ident: #float_type = kani::any();
+        #assumption
+    }
+}
+
+fn init_for_string(
+    arg_name: &str,
+    mutability: &Option<Mut>,
+    opts: &ArgOptions,
+) -> proc_macro2::TokenStream {
+    let string_limit = opts.len.unwrap_or(STRING_LIMIT);
     let arg_ident = quote::format_ident!("{}", arg_name);
     let arr_name = quote::format_ident!("{}_arr", arg_ident);
+    let len_name = quote::format_ident!("{}_len", arg_ident);
+    let init_len = quote! {
+        let #len_name: usize = kani::any();
+        kani::assume(#len_name <= #string_limit);
+    };
+    if let StringMode::Utf8 = opts.string {
+        return quote! {
+            let #arr_name = kani::any::<[u8; #string_limit]>();
+            #init_len
+            kani::assume(std::str::from_utf8(&#arr_name[..#len_name]).is_ok());
+            let #mutability #arg_ident = String::from_utf8(#arr_name[..#len_name].to_vec()).unwrap();
+        };
+    }
+    let char_filter = match char_predicate(&opts.string) {
+        Some(predicate) => quote! {
+            kani::assume(#arr_name.iter().all(|c| #predicate));
+        },
+        None => quote!(),
+    };
     quote! {
-        let #arr_name = kani::any::<[char; #STRING_LIMIT]>();
-        let #mutability #arg_ident = String::from_iter(#arr_name);
+        let #arr_name = kani::any::<[char; #string_limit]>();
+        #char_filter
+        #init_len
+        let #mutability #arg_ident = String::from_iter(&#arr_name[..#len_name]);
+    }
+}
+
+/// The condition on `c: &char` that the characters of a string must satisfy, if any.
+fn char_predicate(mode: &StringMode) -> Option<proc_macro2::TokenStream> {
+    match mode {
+        StringMode::Any | StringMode::Utf8 => None,
+        StringMode::Ascii => Some(quote! { c.is_ascii() }),
+        StringMode::Digit => Some(quote! { c.is_ascii_digit() }),
+        StringMode::Hex => Some(quote! { c.is_ascii_hexdigit() }),
+        StringMode::Alpha => Some(quote! { c.is_ascii_alphabetic() }),
+        StringMode::Alnum => Some(quote! { c.is_ascii_alphanumeric() }),
+        StringMode::Chars(ranges) => {
+            let patterns = ranges.iter().map(|(lo, hi)| {
+                if lo == hi {
+                    quote! { #lo }
+                } else {
+                    quote! { #lo..=#hi }
+                }
+            });
+            Some(quote! { matches!(*c, #(#patterns)|*) })
+        }
     }
 }
 
 impl ArbitraryInit for TypeArray {
-    fn init_for_type(&self, arg_name: &str, mutability: &Option<Mut>) -> proc_macro2::TokenStream {
+    fn init_for_type(
+        &self,
+        arg_name: &str,
+        mutability: &Option<Mut>,
+        _opts: &ArgOptions,
+    ) -> proc_macro2::TokenStream {
         let arr_type = &self.elem;
         let arr_len = &self.len;
         let arg_ident = quote::format_ident!("{}", arg_name);
@@ -311,21 +1204,34 @@ impl ArbitraryInit for TypeArray {
 }
 
 impl ArbitraryInit for TypeSlice {
-    fn init_for_type(&self, arg_name: &str, mutability: &Option<Mut>) -> proc_macro2::TokenStream {
+    fn init_for_type(
+        &self,
+        arg_name: &str,
+        mutability: &Option<Mut>,
+        opts: &ArgOptions,
+    ) -> proc_macro2::TokenStream {
         let slice_type = &self.elem;
         let arg_ident = quote::format_ident!("{}", arg_name);
+        let arr_limit = opts.len.unwrap_or(ARR_LIMIT);
         quote! {
-            let #mutability #arg_ident = kani::any::<[#slice_type; #ARR_LIMIT]>();
+            let #mutability #arg_ident = kani::any::<[#slice_type; #arr_limit]>();
         }
     }
 }
 
 impl ArbitraryInit for TypeReference {
-    fn init_for_type(&self, arg_name: &str, _mutability: &Option<Mut>) -> proc_macro2::TokenStream {
+    fn init_for_type(
+        &self,
+        arg_name: &str,
+        _mutability: &Option<Mut>,
+        opts: &ArgOptions,
+    ) -> proc_macro2::TokenStream {
         let obj_name = quote::format_ident!("{}_obj", arg_name);
         let arg_ident = quote::format_ident!("{}", arg_name);
         let mutability = self.mutability;
-        let obj_init = self.elem.init_for_type(&obj_name.to_string(), &mutability);
+        let obj_init = self
+            .elem
+            .init_for_type(&obj_name.to_string(), &mutability, opts);
         match self.elem.as_ref() {
             Type::Slice(_) => {
                 let slice_method = match mutability {
@@ -347,7 +1253,12 @@ impl ArbitraryInit for TypeReference {
 }
 
 impl ArbitraryInit for TypePtr {
-    fn init_for_type(&self, arg_name: &str, _mutability: &Option<Mut>) -> proc_macro2::TokenStream {
+    fn init_for_type(
+        &self,
+        arg_name: &str,
+        _mutability: &Option<Mut>,
+        _opts: &ArgOptions,
+    ) -> proc_macro2::TokenStream {
         let arg_ident = quote::format_ident!("{}", arg_name);
         let mutability = self.mutability;
         let const_token = self.const_token;
@@ -360,24 +1271,35 @@ impl ArbitraryInit for TypePtr {
 }
 
 impl ArbitraryInit for Type {
-    fn init_for_type(&self, arg_name: &str, mutability: &Option<Mut>) -> proc_macro2::TokenStream {
+    fn init_for_type(
+        &self,
+        arg_name: &str,
+        mutability: &Option<Mut>,
+        opts: &ArgOptions,
+    ) -> proc_macro2::TokenStream {
         match self {
-            Type::Path(type_path) => type_path.init_for_type(arg_name, mutability),
-            Type::Array(type_arr) => type_arr.init_for_type(arg_name, mutability),
-            Type::Slice(type_slice) => type_slice.init_for_type(arg_name, mutability),
-            Type::Tuple(type_tuple) => type_tuple.init_for_type(arg_name, mutability),
-            Type::Reference(type_ref) => type_ref.init_for_type(arg_name, mutability),
-            Type::Ptr(type_ptr) => type_ptr.init_for_type(arg_name, mutability),
-            _ => error_msg("Unsupported argument type for `kani_test` macro."),
+            Type::Path(type_path) => type_path.init_for_type(arg_name, mutability, opts),
+            Type::Array(type_arr) => type_arr.init_for_type(arg_name, mutability, opts),
+            Type::Slice(type_slice) => type_slice.init_for_type(arg_name, mutability, opts),
+            Type::Tuple(type_tuple) => type_tuple.init_for_type(arg_name, mutability, opts),
+            Type::Reference(type_ref) => type_ref.init_for_type(arg_name, mutability, opts),
+            Type::Ptr(type_ptr) => type_ptr.init_for_type(arg_name, mutability, opts),
+            _ => syn::Error::new_spanned(self, "autokani cannot generate values of this type")
+                .to_compile_error(),
         }
     }
 }
 
 impl ArbitraryInit for TypeTuple {
-    fn init_for_type(&self, arg_name: &str, mutability: &Option<Mut>) -> proc_macro2::TokenStream {
+    fn init_for_type(
+        &self,
+        arg_name: &str,
+        mutability: &Option<Mut>,
+        opts: &ArgOptions,
+    ) -> proc_macro2::TokenStream {
         let tuple_elems = self.elems.iter().map(|elem| {
             let elem_name = quote::format_ident!("{}_elem", arg_name);
-            let elem_init = elem.init_for_type(&elem_name.to_string(), mutability);
+            let elem_init = elem.init_for_type(&elem_name.to_string(), mutability, opts);
             quote! {
                 {
                     #elem_init
@@ -397,71 +1319,372 @@ impl ArbitraryInit for TypeTuple {
 ///
 /// Since some common types (e.g., Vec) do not impl `Arbitrary`, it's inpractical to derive `Arbitrary`.
 /// Instead, this macro generates the impl block for `Arbitrary`.
-pub fn kani_arbitrary(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(item as Item);
-    let struct_def = match input {
-        Item::Struct(ref struct_def) => struct_def,
+///
+/// On an enum, the `any` method picks one of the variants nondeterministically
+/// and generates its fields the same way.
+///
+/// Accepts `integers = strategy` and `floats = mode` to constrain the integer and float fields,
+/// see [`macro@autokani_func`].
+///
+/// `invariant = pred` assumes `pred(&value)` on every generated value, where `pred` is a path
+/// such as `Self::is_valid` or a closure over `&Self`. With `impl_invariant`,
+/// `kani::Invariant` is implemented for the type as well, with `pred` as `is_safe`.
+///
+/// Reference fields point to a generated value leaked with `Box::leak`, so they satisfy
+/// any lifetime. References nested in other types, e.g. `Option<&'a T>`, must be set
+/// through `value` or `with`.
+///
+/// # Field attributes
+/// A field can be tuned with the `#[autokani(..)]` helper attribute:
+/// - `range = lo..hi`, `len = N`, `string = mode` and `float = mode`: as the arguments of
+///   the same name of [`macro@autokani_func`], for this field only.
+/// - `value = expr`: the field is set to `expr`.
+/// - `default`: the field is set to `Default::default()`.
+/// - `with = path`: the field is set to the result of calling `path()`.
+///
+/// Fields are generated in declaration order and these attributes may refer to the fields
+/// declared before by name (`field_0`, `field_1`, .. in tuple structs and variants),
+/// e.g. to keep related fields consistent:
+/// ```rust,ignore
+/// use autokani::kani_arbitrary;
+/// #[kani_arbitrary]
+/// pub struct Buffer {
+///     #[autokani(range = 0..=16)]
+///     capacity: usize,
+///     #[autokani(range = 0..=capacity)]
+///     len: usize,
+///     #[autokani(value = Vec::with_capacity(capacity))]
+///     data: Vec<u8>,
+/// }
+/// ```
+pub fn kani_arbitrary(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as ArbitraryArgs);
+    if let Some(key) = &args.impl_key {
+        return syn::Error::new(
+            key.span(),
+            format!("`{}` only applies to `extend_arbitrary`", key),
+        )
+        .to_compile_error()
+        .into();
+    }
+    let mut input = parse_macro_input!(item as Item);
+    let impl_stmt = match &mut input {
+        Item::Struct(struct_def) => {
+            let impl_stmt = impl_arbitrary_via_fields(struct_def, &args);
+            attr::strip_field_attrs(&mut struct_def.fields);
+            impl_stmt
+        }
+        Item::Enum(enum_def) => {
+            let impl_stmt = impl_arbitrary_via_variants(enum_def, &args);
+            for variant in &mut enum_def.variants {
+                attr::strip_field_attrs(&mut variant.fields);
+            }
+            impl_stmt
+        }
         _ => {
-            return error_msg("`kani_arbitrary` can only be used on structs.").into();
+            return error_msg("`kani_arbitrary` can only be used on structs and enums.").into();
         }
     };
-    let impl_stmt = impl_arbitrary_via_fields(&struct_def);
     let output = quote! {
-        #struct_def
+        #input
 
         #impl_stmt
     };
     output.into()
 }
 
-fn impl_arbitrary_via_fields(struct_def: &ItemStruct) -> proc_macro2::TokenStream {
-    let mutability: Option<Mut> = None;
+fn impl_arbitrary_via_fields(
+    struct_def: &ItemStruct,
+    args: &ArbitraryArgs,
+) -> proc_macro2::TokenStream {
     let struct_name = &struct_def.ident;
-    let init_stmt = match &struct_def.fields {
-        syn::Fields::Named(fields) => {
-            let mut fields_init: Vec<proc_macro2::TokenStream> = Vec::new();
-            for field in &fields.named {
-                let field_name = field.ident.as_ref().unwrap();
-                let field_type = &field.ty;
-                let obj = field_type.init_for_type(&field_name.to_string(), &mutability);
-                let field_init = quote! {
-                    #field_name: {
-                        #obj
-                        #field_name
-                    }
-                };
-                fields_init.push(field_init);
+    let init_stmt = init_fields(quote! { Self }, &struct_def.fields, &args.field_options());
+    let generics = generics::with_arbitrary_bounds(
+        &struct_def.generics,
+        struct_def.fields.iter().map(|field| &field.ty),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let init_stmt = assume_invariant(args, init_stmt);
+    let invariant_impl = impl_invariant(
+        args,
+        quote! { #impl_generics },
+        quote! { #struct_name #ty_generics },
+        quote! { #where_clause },
+    );
+    quote! {
+        /// Arbitrary impl Generated by autokani
+        #[cfg(any(kani, feature = "debug_log"))]
+        impl #impl_generics kani::Arbitrary for #struct_name #ty_generics #where_clause {
+            /// Automatically generate the `any` method based on fields
+            fn any() -> Self {
+                #init_stmt
             }
+        }
 
-            quote! {
-                Self {
-                    #(#fields_init),*
-                }
+        #invariant_impl
+    }
+}
+
+fn impl_arbitrary_via_variants(
+    enum_def: &ItemEnum,
+    args: &ArbitraryArgs,
+) -> proc_macro2::TokenStream {
+    let enum_name = &enum_def.ident;
+    let variant_count = enum_def.variants.len();
+    if variant_count == 0 {
+        return syn::Error::new_spanned(
+            enum_name,
+            "`kani_arbitrary` cannot generate a value of an enum without variants",
+        )
+        .to_compile_error();
+    }
+    let field_opts = args.field_options();
+    // The last variant takes the remaining discriminants.
+    let variant_arms = enum_def
+        .variants
+        .iter()
+        .enumerate()
+        .map(|(index, variant)| {
+            let variant_name = &variant.ident;
+            let init_stmt =
+                init_fields(quote! { Self::#variant_name }, &variant.fields, &field_opts);
+            if index + 1 == variant_count {
+                quote! { _ => #init_stmt }
+            } else {
+                let index = proc_macro2::Literal::usize_unsuffixed(index);
+                quote! { #index => #init_stmt }
             }
-        }
-        syn::Fields::Unnamed(_fields) => {
-            todo!("Fields::Unnamed");
-        }
-        syn::Fields::Unit => {
-            todo!("Fields::Unit");
-        }
+        });
+    let generics = generics::with_arbitrary_bounds(
+        &enum_def.generics,
+        enum_def
+            .variants
+            .iter()
+            .flat_map(|variant| &variant.fields)
+            .map(|field| &field.ty),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let discriminant_type = if variant_count <= 1 << 8 {
+        quote! { u8 }
+    } else if variant_count <= 1 << 16 {
+        quote! { u16 }
+    } else {
+        quote! { u32 }
     };
+    let init_stmt = assume_invariant(
+        args,
+        quote! {
+            match kani::any::<#discriminant_type>() {
+                #(#variant_arms),*
+            }
+        },
+    );
+    let invariant_impl = impl_invariant(
+        args,
+        quote! { #impl_generics },
+        quote! { #enum_name #ty_generics },
+        quote! { #where_clause },
+    );
     quote! {
         /// Arbitrary impl Generated by autokani
         #[cfg(any(kani, feature = "debug_log"))]
-        impl kani::Arbitrary for #struct_name {
-            /// Automatically generate the `any` method based on fields
+        impl #impl_generics kani::Arbitrary for #enum_name #ty_generics #where_clause {
+            /// Automatically generate the `any` method based on variants
             fn any() -> Self {
                 #init_stmt
             }
         }
+
+        #invariant_impl
+    }
+}
+
+/// Assume the `invariant` of `args`, if any, on the value built by `init_stmt`.
+fn assume_invariant(
+    args: &ArbitraryArgs,
+    init_stmt: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    match &args.invariant {
+        Some(invariant) => quote! {
+            let value: Self = #init_stmt;
+            kani::assume((#invariant)(&value));
+            value
+        },
+        None => init_stmt,
+    }
+}
+
+/// Implement `kani::Invariant` for `self_ty` with the `invariant` of `args`,
+/// if asked to by `impl_invariant`.
+fn impl_invariant(
+    args: &ArbitraryArgs,
+    impl_generics: proc_macro2::TokenStream,
+    self_ty: proc_macro2::TokenStream,
+    where_clause: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    match &args.invariant {
+        Some(invariant) if args.impl_invariant => quote! {
+            /// Invariant impl Generated by autokani
+            #[cfg(any(kani, feature = "debug_log"))]
+            impl #impl_generics kani::Invariant for #self_ty #where_clause {
+                fn is_safe(&self) -> bool {
+                    (#invariant)(self)
+                }
+            }
+        },
+        _ => quote!(),
+    }
+}
+
+/// Construct `path` (e.g. `Self` or `Self::Variant`) with its `fields` generated by
+/// `init_for_type`, unless their `#[autokani(..)]` attribute says otherwise.
+/// The fields are generated in declaration order, and bound to their names (`field_{index}`
+/// for tuple fields) so the attributes of a field can refer to the fields declared before.
+fn init_fields(
+    path: proc_macro2::TokenStream,
+    fields: &syn::Fields,
+    opts: &ArgOptions,
+) -> proc_macro2::TokenStream {
+    let mutability: Option<Mut> = None;
+    let mut fields_init = Vec::new();
+    let mut field_names = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let field_name = match &field.ident {
+            Some(ident) => ident.clone(),
+            None => quote::format_ident!("field_{}", index),
+        };
+        let field_args = match FieldArgs::from_attrs(&field.attrs) {
+            Ok(field_args) => field_args,
+            Err(err) => return err.to_compile_error(),
+        };
+        let field_type = &field.ty;
+        let field_value = match &field_args.source {
+            Some(FieldSource::Value(value)) => quote! { #value },
+            Some(FieldSource::Default) => quote! { ::core::default::Default::default() },
+            Some(FieldSource::With(generator)) => quote! { #generator() },
+            None if !matches!(field_type, Type::Reference(_)) && has_reference(field_type) => {
+                return syn::Error::new_spanned(
+                    field_type,
+                    "`kani_arbitrary` cannot generate a reference inside another type, \
+                     set this field with `#[autokani(value = ..)]` or `#[autokani(with = ..)]`",
+                )
+                .to_compile_error();
+            }
+            None => {
+                let field_opts = field_args.options(opts);
+                if let Type::Reference(type_ref) = field_type {
+                    fields_init.push(init_leaked_reference(type_ref, &field_name, &field_opts));
+                    field_names.push(field_name);
+                    continue;
+                }
+                let obj =
+                    field_type.init_for_type(&field_name.to_string(), &mutability, &field_opts);
+                quote! {
+                    {
+                        #obj
+                        #field_name
+                    }
+                }
+            }
+        };
+        fields_init.push(quote! {
+            let #field_name: #field_type = #field_value;
+        });
+        field_names.push(field_name);
+    }
+    let construct = match fields {
+        syn::Fields::Named(_) => quote! { #path { #(#field_names),* } },
+        syn::Fields::Unnamed(_) => quote! { #path(#(#field_names),*) },
+        syn::Fields::Unit => return path,
+    };
+    quote! {
+        {
+            #(#fields_init)*
+            #construct
+        }
+    }
+}
+
+/// Generate the reference field `field_name`, whose referent is leaked
+/// so that it outlives `any` for whatever lifetime the field has.
+fn init_leaked_reference(
+    type_ref: &TypeReference,
+    field_name: &Ident,
+    opts: &ArgOptions,
+) -> proc_macro2::TokenStream {
+    let obj_name = quote::format_ident!("{}_obj", field_name);
+    let obj_init = type_ref
+        .elem
+        .init_for_type(&obj_name.to_string(), &None, opts);
+    let leaked = match &*type_ref.elem {
+        Type::Slice(_) => {
+            let slice_method = match type_ref.mutability {
+                Some(_) => quote! { kani::slice::any_slice_of_array_mut },
+                None => quote! { kani::slice::any_slice_of_array },
+            };
+            quote! { #slice_method(Box::leak(Box::new(#obj_name))) }
+        }
+        Type::Path(type_path) if type_path.path.is_ident("str") => {
+            quote! { Box::leak(#obj_name.into_boxed_str()) }
+        }
+        _ => quote! { Box::leak(Box::new(#obj_name)) },
+    };
+    let field_type = Type::Reference(type_ref.clone());
+    quote! {
+        let #field_name: #field_type = {
+            #obj_init
+            #leaked
+        };
+    }
+}
+
+/// Whether `ty` holds a reference, which `init_for_type` binds to a local.
+fn has_reference(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(_) => true,
+        Type::Path(type_path) => match type_path.path.segments.last() {
+            // `PhantomData` is generated without its type argument
+            Some(segment) if segment.ident != "PhantomData" => match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .any(|arg| matches!(arg, syn::GenericArgument::Type(ty) if has_reference(ty))),
+                _ => false,
+            },
+            _ => false,
+        },
+        Type::Array(type_arr) => has_reference(&type_arr.elem),
+        Type::Slice(type_slice) => has_reference(&type_slice.elem),
+        Type::Tuple(type_tuple) => type_tuple.elems.iter().any(has_reference),
+        Type::Paren(type_paren) => has_reference(&type_paren.elem),
+        _ => false,
     }
 }
 
 /// Extend the `Arbitrary` trait for target struct based on its constructor(e.g., `new` method).
 /// Add this attribute to the impl block of the struct.
+///
+/// By default the first associated function returning `Self` is the constructor.
+/// `ctor = name` chooses another one, and `ctors = [new, from_parts]` picks one of several
+/// constructors nondeterministically. Their arguments are arbitrary.
+///
+/// A constructor may also be fallible, returning `Result<Self, _>` or `Option<Self>`:
+/// its success is assumed, so only the values the constructor accepts are generated.
+/// Infallible constructors are preferred when none is chosen.
+///
+/// With `steps = N`, the constructed value then goes through a nondeterministic sequence
+/// of up to `N` calls to the public `&mut self` methods of the impl block, with arbitrary
+/// arguments, so that states reachable through the API are generated too. Methods taking
+/// another `Self`, e.g. `append(&mut self, other: &mut Self)`, are skipped: generating it
+/// would recurse without bound.
+/// `include = [push, pop]` restricts the methods called, `exclude = [clear]` leaves some out.
+///
+/// Accepts `integers = strategy` and `floats = mode` to constrain the integer and float
+/// arguments of the constructor, see [`macro@autokani_func`], as well as `invariant = pred`
+/// and `impl_invariant`, see [`macro@kani_arbitrary`].
 #[proc_macro_attribute]
-pub fn extend_arbitrary(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn extend_arbitrary(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as ArbitraryArgs);
     let input = parse_macro_input!(item as Item);
     let impl_block = match input {
         Item::Impl(impl_block) => impl_block,
@@ -469,7 +1692,7 @@ pub fn extend_arbitrary(_attr: TokenStream, item: TokenStream) -> TokenStream {
             return error_msg("`extend_arbitrary` can only be used on impl blocks.").into();
         }
     };
-    let impl_arbitrary = impl_arbitrary_via_constructor(&impl_block);
+    let impl_arbitrary = impl_arbitrary_via_constructor(&impl_block, &args);
     let output = quote! {
         #impl_block
         #impl_arbitrary
@@ -477,84 +1700,1085 @@ pub fn extend_arbitrary(_attr: TokenStream, item: TokenStream) -> TokenStream {
     output.into()
 }
 
-fn find_constructor(impl_block: &ItemImpl, struct_name: Option<&Ident>) -> Option<ImplItemMethod> {
-    for item in &impl_block.items {
-        if let ImplItem::Method(method) = item {
-            if let ReturnType::Type(_, return_type) = &method.sig.output {
-                if let Type::Path(type_path) = &**return_type {
-                    if type_path.path.is_ident("Self") {
-                        return Some(method.clone());
-                    }
-                    if let Some(name) = struct_name {
-                        if type_path.path.is_ident(name) {
-                            return Some(method.clone());
-                        }
-                    }
-                }
-            }
+/// How a constructor returns the constructed value.
+#[derive(Clone, Copy, PartialEq)]
+enum CtorKind {
+    /// `Self`
+    Infallible,
+    /// `Result<Self, _>`
+    Result,
+    /// `Option<Self>`
+    Option,
+}
+
+/// How `method` constructs the type of the impl block, if it is a constructor:
+/// an associated function returning `Self` (or the type by name), possibly wrapped
+/// in a `Result` or an `Option`.
+fn constructor_kind(method: &ImplItemMethod, struct_name: Option<&Ident>) -> Option<CtorKind> {
+    if method.sig.receiver().is_some() {
+        return None;
+    }
+    let is_self = |ty: &Type| match ty {
+        Type::Path(type_path) => {
+            let final_seg = type_path.path.segments.last();
+            type_path.path.is_ident("Self")
+                || struct_name
+                    .is_some_and(|name| final_seg.is_some_and(|segment| segment.ident == *name))
         }
+        _ => false,
+    };
+    let return_type = match &method.sig.output {
+        ReturnType::Type(_, return_type) => &**return_type,
+        ReturnType::Default => return None,
+    };
+    if is_self(return_type) {
+        return Some(CtorKind::Infallible);
+    }
+    let final_seg = match return_type {
+        Type::Path(type_path) => type_path.path.segments.last()?,
+        _ => return None,
+    };
+    let wrapped = match &final_seg.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(syn::GenericArgument::Type(ty)) => ty,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    if !is_self(wrapped) {
+        return None;
+    }
+    if final_seg.ident == "Result" {
+        Some(CtorKind::Result)
+    } else if final_seg.ident == "Option" {
+        Some(CtorKind::Option)
+    } else {
+        None
     }
-    None
 }
 
-fn impl_arbitrary_via_constructor(impl_block: &ItemImpl) -> Option<proc_macro2::TokenStream> {
-    // This function should generate the `Arbitrary` impl block based on the `new` method.
-    // You need to parse the `new` method and generate the corresponding `Arbitrary` impl block.
+/// The constructors named by `ctor`/`ctors`, or else the first constructor of the impl block.
+fn find_constructors<'a>(
+    impl_block: &'a ItemImpl,
+    struct_name: Option<&Ident>,
+    args: &ArbitraryArgs,
+) -> syn::Result<Vec<&'a ImplItemMethod>> {
+    let methods = impl_block.items.iter().filter_map(|item| match item {
+        ImplItem::Method(method) => Some(method),
+        _ => None,
+    });
+    if args.ctors.is_empty() {
+        // infallible constructors first, they need no assumption
+        let infallible = methods
+            .clone()
+            .find(|method| constructor_kind(method, struct_name) == Some(CtorKind::Infallible));
+        let fallible = methods
+            .clone()
+            .find(|method| constructor_kind(method, struct_name).is_some());
+        return match infallible.or(fallible) {
+            Some(constructor) => Ok(vec![constructor]),
+            None => Err(syn::Error::new_spanned(
+                &impl_block.self_ty,
+                "`extend_arbitrary` found no constructor in this impl block, \
+                 add an associated function returning `Self`, `Result<Self, _>` or `Option<Self>`",
+            )),
+        };
+    }
+    args.ctors
+        .iter()
+        .map(|name| {
+            let method = methods
+                .clone()
+                .find(|method| method.sig.ident == *name)
+                .ok_or_else(|| {
+                    syn::Error::new(
+                        name.span(),
+                        format!("no method named `{}` in this impl block", name),
+                    )
+                })?;
+            if constructor_kind(method, struct_name).is_none() {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "`{}` is not a constructor, it must take no `self` and return `Self`, \
+                         `Result<Self, _>` or `Option<Self>`",
+                        name
+                    ),
+                ));
+            }
+            Ok(method)
+        })
+        .collect()
+}
+
+fn impl_arbitrary_via_constructor(
+    impl_block: &ItemImpl,
+    args: &ArbitraryArgs,
+) -> proc_macro2::TokenStream {
     let struct_name = match &*impl_block.self_ty {
-        Type::Path(type_path) => type_path.path.get_ident(),
+        Type::Path(type_path) => type_path.path.segments.last().map(|segment| &segment.ident),
         _ => {
-            return error_msg("`extend_arbitrary` can only be used on impl blocks of structs.")
-                .into();
+            return error_msg("`extend_arbitrary` can only be used on impl blocks of structs.");
         }
     };
-    // let constructor = find_constructor(impl_block, struct_name)?;
-    let constructor = match find_constructor(impl_block, struct_name) {
-        Some(constructor) => constructor,
-        None => {
-            return Some(quote! {
-                    impl kani::Arbitrary for Placeholder222 {
-                    }
-            });
+    let constructors = match find_constructors(impl_block, struct_name, args) {
+        Ok(constructors) => constructors,
+        Err(err) => return err.to_compile_error(),
+    };
+    let calls: Vec<proc_macro2::TokenStream> = constructors
+        .iter()
+        .map(|constructor| call_constructor(constructor, struct_name, args))
+        .collect();
+    let construct = match calls.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, calls)) => {
+            let indices = (0..calls.len()).map(proc_macro2::Literal::usize_unsuffixed);
+            quote! {
+                match kani::any::<u8>() {
+                    #(#indices => #calls,)*
+                    _ => #last,
+                }
+            }
         }
+        None => unreachable!("`find_constructors` returns at least one constructor"),
+    };
+    let mutators = match args.steps {
+        Some(_) => match find_mutators(impl_block, struct_name, args) {
+            Ok(mutators) => mutators,
+            Err(err) => return err.to_compile_error(),
+        },
+        None => Vec::new(),
     };
-    let inputs = &constructor.sig.inputs;
+    let construct = match args.steps {
+        Some(steps) => call_mutators(construct, &mutators, steps, args),
+        None => construct,
+    };
+    let generics = generics::with_arbitrary_bounds(
+        &impl_block.generics,
+        constructors
+            .iter()
+            .chain(&mutators)
+            .flat_map(|method| &method.sig.inputs)
+            .filter_map(|arg| match arg {
+                FnArg::Typed(pat_type) => Some(&*pat_type.ty),
+                FnArg::Receiver(_) => None,
+            }),
+    );
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let self_ty = &impl_block.self_ty;
+    let init_stmt = assume_invariant(args, construct);
+    let invariant_impl = impl_invariant(
+        args,
+        quote! { #impl_generics },
+        quote! { #self_ty },
+        quote! { #where_clause },
+    );
+    quote! {
+        /// Arbitrary impl Generated by autokani
+        #[cfg(any(kani, feature = "debug_log"))]
+        impl #impl_generics kani::Arbitrary for #self_ty #where_clause {
+            /// Automatically generate the `any` method based on constructor
+            fn any() -> Self {
+                #init_stmt
+            }
+        }
+
+        #invariant_impl
+    }
+}
+
+/// Call `constructor` with arbitrary arguments,
+/// assuming that it succeeds if it returns a `Result` or an `Option`.
+fn call_constructor(
+    constructor: &ImplItemMethod,
+    struct_name: Option<&Ident>,
+    args: &ArbitraryArgs,
+) -> proc_macro2::TokenStream {
     let func_name = &constructor.sig.ident;
+    let (init_code, call_args) = init_call_args(constructor, args);
+    let call = quote! { Self::#func_name(#(#call_args),*) };
+    let construct = match constructor_kind(constructor, struct_name) {
+        Some(CtorKind::Result) => quote! {
+            let constructed = #call.ok();
+            kani::assume(constructed.is_some());
+            constructed.unwrap()
+        },
+        Some(CtorKind::Option) => quote! {
+            let constructed = #call;
+            kani::assume(constructed.is_some());
+            constructed.unwrap()
+        },
+        _ => call,
+    };
+    quote! {
+        {
+            #(#init_code)*
+            #construct
+        }
+    }
+}
+
+/// Generate arbitrary values for the arguments of `method` but its receiver,
+/// returning the statements and the arguments of the call.
+fn init_call_args(
+    method: &ImplItemMethod,
+    args: &ArbitraryArgs,
+) -> (Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>) {
     let mut init_code = Vec::new();
     let mut call_args: Vec<proc_macro2::TokenStream> = Vec::new();
 
-    for arg in inputs {
-        match arg {
-            FnArg::Receiver(_) => unreachable!(),
-            FnArg::Typed(pat_type) => {
-                let arg_name = match &*pat_type.pat {
-                    syn::Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
-                    _ => "arg".to_string(),
-                };
-                let arg_type = &pat_type.ty;
-                let mutability = match &*pat_type.pat {
-                    Pat::Ident(pat_ident) => pat_ident.mutability,
-                    _ => None,
-                };
-                let init_stmt = arg_type.init_for_type(&arg_name, &mutability);
-                init_code.push(init_stmt);
+    for arg in &method.sig.inputs {
+        if let FnArg::Typed(pat_type) = arg {
+            let arg_name = match &*pat_type.pat {
+                syn::Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
+                _ => "arg".to_string(),
+            };
+            let arg_type = &pat_type.ty;
+            let mutability = match &*pat_type.pat {
+                Pat::Ident(pat_ident) => pat_ident.mutability,
+                _ => None,
+            };
+            let init_stmt = arg_type.init_for_type(&arg_name, &mutability, &args.field_options());
+            init_code.push(init_stmt);
 
-                let arg_ident = quote::format_ident!("{}", arg_name);
-                call_args.push(quote! { #arg_ident });
+            let arg_ident = quote::format_ident!("{}", arg_name);
+            call_args.push(quote! { #arg_ident });
+        }
+    }
+    (init_code, call_args)
+}
+
+/// Whether `method` takes an argument whose type mentions `Self` or the type of the impl block,
+/// e.g. `other: &mut Self`: generating it would make `kani::any` recurse without bound.
+fn takes_self_arg(method: &ImplItemMethod, struct_name: Option<&Ident>) -> bool {
+    method.sig.inputs.iter().any(|arg| match arg {
+        FnArg::Typed(pat_type) => {
+            let ty = &pat_type.ty;
+            contract::mentions_ident(quote! { #ty }, "Self")
+                || struct_name
+                    .is_some_and(|name| contract::mentions_ident(quote! { #ty }, &name.to_string()))
+        }
+        FnArg::Receiver(_) => false,
+    })
+}
+
+/// Whether `method` may be called in the `steps` of `extend_arbitrary`: a public and safe
+/// `&mut self` method without type or const parameters, nor arguments of type `Self`.
+fn is_mutator(method: &ImplItemMethod, struct_name: Option<&Ident>) -> bool {
+    let mut_receiver = matches!(
+        method.sig.receiver(),
+        Some(FnArg::Receiver(Receiver {
+            reference: Some(_),
+            mutability: Some(_),
+            ..
+        }))
+    );
+    let has_generics = method
+        .sig
+        .generics
+        .params
+        .iter()
+        .any(|param| !matches!(param, syn::GenericParam::Lifetime(_)));
+    mut_receiver
+        && matches!(method.vis, syn::Visibility::Public(_))
+        && method.sig.unsafety.is_none()
+        && method.sig.asyncness.is_none()
+        && !has_generics
+        && !takes_self_arg(method, struct_name)
+}
+
+/// The methods called in the `steps` of `extend_arbitrary`: the `include`d ones,
+/// or else every mutator of the impl block, without the `exclude`d ones.
+fn find_mutators<'a>(
+    impl_block: &'a ItemImpl,
+    struct_name: Option<&Ident>,
+    args: &ArbitraryArgs,
+) -> syn::Result<Vec<&'a ImplItemMethod>> {
+    let methods: Vec<&ImplItemMethod> = impl_block
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Method(method) => Some(method),
+            _ => None,
+        })
+        .collect();
+    let find_method = |name: &Ident| {
+        methods
+            .iter()
+            .copied()
+            .find(|method| method.sig.ident == *name)
+            .ok_or_else(|| {
+                syn::Error::new(
+                    name.span(),
+                    format!("no method named `{}` in this impl block", name),
+                )
+            })
+    };
+    for name in &args.exclude {
+        find_method(name)?;
+    }
+    let mutators: Vec<&ImplItemMethod> = if args.include.is_empty() {
+        methods
+            .iter()
+            .copied()
+            .filter(|method| is_mutator(method, struct_name))
+            .filter(|method| !args.exclude.contains(&method.sig.ident))
+            .collect()
+    } else {
+        args.include
+            .iter()
+            .filter(|name| !args.exclude.contains(name))
+            .map(|name| {
+                let method = find_method(name)?;
+                if takes_self_arg(method, struct_name) {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!(
+                            "`{}` cannot be called in the steps, generating its arguments of \
+                             type `Self` would make `kani::any` recurse without bound",
+                            name
+                        ),
+                    ));
+                }
+                if !is_mutator(method, struct_name) {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!(
+                            "`{}` cannot be called in the steps, it must be a public and safe \
+                             `&mut self` method without type parameters",
+                            name
+                        ),
+                    ));
+                }
+                Ok(method)
+            })
+            .collect::<syn::Result<_>>()?
+    };
+    if mutators.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &impl_block.self_ty,
+            "`steps` found no public `&mut self` method to call in this impl block",
+        ));
+    }
+    Ok(mutators)
+}
+
+/// Build the value with `construct`, then call up to `steps` of the `mutators`
+/// in a nondeterministic sequence, each with arbitrary arguments.
+fn call_mutators(
+    construct: proc_macro2::TokenStream,
+    mutators: &[&ImplItemMethod],
+    steps: usize,
+    args: &ArbitraryArgs,
+) -> proc_macro2::TokenStream {
+    let receiver = quote::format_ident!("{}", SELF_RECEIVER);
+    let calls = mutators.iter().enumerate().map(|(index, method)| {
+        let index = proc_macro2::Literal::usize_unsuffixed(index);
+        let method_name = &method.sig.ident;
+        let (init_code, call_args) = init_call_args(method, args);
+        quote! {
+            #index => {
+                #(#init_code)*
+                let _ = #receiver.#method_name(#(#call_args),*);
             }
         }
+    });
+    // every step picks a mutator or nothing, so shorter sequences are covered too
+    let step = quote! {
+        match kani::any::<u8>() {
+            #(#calls)*
+            _ => {}
+        }
+    };
+    let steps = std::iter::repeat_n(step, steps);
+    quote! {
+        {
+            let mut #receiver: Self = #construct;
+            #(#steps)*
+            #receiver
+        }
     }
-    let impl_generics = &impl_block.generics;
-    let self_ty = &impl_block.self_ty;
-    let where_clause = &impl_block.generics.where_clause;
-    Some(quote! {
-        /// Arbitrary impl Generated by autokani
-        #[cfg(any(kani, feature = "debug_log"))]
-        impl #impl_generics kani::Arbitrary for #self_ty #where_clause {
-            /// Automatically generate the `any` method based on constructor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module_name(text: &str) -> String {
+        let impl_block: ItemImpl = syn::parse_str(text).unwrap();
+        harness_module_name(&impl_block).to_string()
+    }
+
+    #[test]
+    fn module_names_follow_the_impl_header() {
+        assert_eq!(module_name("impl Array {}"), "autokani_array");
+        assert_eq!(module_name("impl HttpServer {}"), "autokani_http_server");
+        assert_eq!(module_name("impl<T> Stack<u8> {}"), "autokani_stack_u8");
+        assert_eq!(module_name("impl crate::io::Buf {}"), "autokani_buf");
+        assert_eq!(
+            module_name("impl Index<usize> for Array {}"),
+            "autokani_array_index_usize"
+        );
+        assert_eq!(module_name("impl [u8] {}"), "autokani_u8");
+    }
+
+    #[test]
+    fn type_names_in_trait_harnesses() {
+        let name = |text: &str| type_name_part(&syn::parse_str(text).unwrap());
+        assert_eq!(name("VecStore"), "VecStore");
+        assert_eq!(name("SlotStore<u8>"), "SlotStore_u8");
+        assert_eq!(name("&[u8; 4]"), "u8_4");
+    }
+
+    #[test]
+    fn string_character_predicates() {
+        let predicate = |mode: StringMode| char_predicate(&mode).map(|p| p.to_string());
+        assert_eq!(predicate(StringMode::Any), None);
+        assert_eq!(predicate(StringMode::Utf8), None);
+        assert_eq!(
+            predicate(StringMode::Hex),
+            Some(quote! { c.is_ascii_hexdigit() }.to_string())
+        );
+        assert_eq!(
+            predicate(StringMode::Chars(vec![('0', '9'), ('_', '_')])),
+            Some(quote! { matches!(*c, '0'..='9' | '_') }.to_string())
+        );
+    }
+
+    /// The statements generating the argument `x` of type `ty` for the harness arguments `args`.
+    fn init_x(ty: &str, args: &str) -> String {
+        let args: HarnessArgs = syn::parse_str(args).unwrap();
+        let ty: Type = syn::parse_str(ty).unwrap();
+        ty.init_for_type("x", &None, &args.arg_options("x"))
+            .to_string()
+    }
+
+    #[test]
+    fn integer_ranges() {
+        assert_eq!(
+            init_x("u32", "range(x = 0..=255)"),
+            quote! {
+                let x: u32 = kani::any();
+                kani::assume((0..=255).contains(&x));
+            }
+            .to_string()
+        );
+        assert_eq!(
+            init_x("u64", "range(x = ..)"),
+            quote! { let x: u64 = kani::any(); }.to_string()
+        );
+        // without a range, the default limit applies to the types which may exceed it
+        assert_eq!(
+            init_x("i64", ""),
+            quote! {
+                let x: i64 = kani::any();
+                kani::assume(x < 100000000 && x > -100000000);
+            }
+            .to_string()
+        );
+        assert_eq!(
+            init_x("u128", ""),
+            quote! {
+                let x: u128 = kani::any();
+                kani::assume(x < 100000000);
+            }
+            .to_string()
+        );
+        assert_eq!(
+            init_x("i16", ""),
+            quote! { let x: i16 = kani::any(); }.to_string()
+        );
+    }
+
+    #[test]
+    fn integer_strategies() {
+        assert_eq!(
+            init_x("u32", "integers = full"),
+            quote! { let x: u32 = kani::any(); }.to_string()
+        );
+        assert_eq!(
+            init_x("i8", "integers = boundary"),
+            quote! {
+                let x: i8 = kani::any();
+                kani::assume(
+                    x == 0 || x == -1 || x.count_ones() == 1 || x == i8::MAX || x == i8::MAX - 1
+                );
+            }
+            .to_string()
+        );
+        assert_eq!(
+            init_x("usize", "integers = boundary"),
+            quote! {
+                let x: usize = kani::any();
+                kani::assume(x == 0 || x.count_ones() == 1 || x == usize::MAX || x == usize::MAX - 1);
+            }
+            .to_string()
+        );
+        // a range takes precedence over the strategy
+        assert_eq!(
+            init_x("u32", "integers = boundary, range(x = 1..4)"),
+            quote! {
+                let x: u32 = kani::any();
+                kani::assume((1..4).contains(&x));
+            }
+            .to_string()
+        );
+        // the strategy applies inside tuples and options
+        assert!(init_x("(u8, Option<u64>)", "integers = boundary")
+            .contains(&quote! { x_elem == u64::MAX }.to_string()));
+    }
+
+    #[test]
+    fn float_modes() {
+        assert_eq!(
+            init_x("f32", ""),
+            quote! { let x: f32 = kani::any(); }.to_string()
+        );
+        assert_eq!(
+            init_x("f64", "floats = not_nan, float(x = finite)"),
+            quote! {
+                let x: f64 = kani::any();
+                kani::assume(x.is_finite());
+            }
+            .to_string()
+        );
+        assert_eq!(
+            init_x("f64", "floats = -1e6..1e6"),
+            quote! {
+                let x: f64 = kani::any();
+                kani::assume((-1e6..1e6).contains(&x));
+            }
+            .to_string()
+        );
+    }
+
+    /// The harness generated for `func` with the harness arguments `args`.
+    fn harness(args: &str, func: &str, contract: bool) -> String {
+        let args: HarnessArgs = syn::parse_str(args).unwrap();
+        let func: syn::ItemFn = syn::parse_str(func).unwrap();
+        let inst =
+            &generics::instantiations(&func.sig.generics, &args.instantiate, contract).unwrap()[0];
+        let name = quote::format_ident!("check_{}", func.sig.ident);
+        harness_for_instantiation(&args, &func, inst, &name, CallStyle::Func, contract).to_string()
+    }
+
+    #[test]
+    fn contract_harnesses_leave_integers_unconstrained() {
+        let func = "fn scale(x: u64, y: u64) -> u64 { x }";
+        let bound = quote! { kani::assume(x < 100000000); }.to_string();
+        assert!(harness("", func, false).contains(&bound));
+        let contract = harness("", func, true);
+        assert!(contract.contains(&quote! { kani::proof_for_contract(scale) }.to_string()));
+        assert!(!contract.contains(&bound));
+        assert!(harness("integers = bounded", func, true).contains(&bound));
+    }
+
+    #[test]
+    fn contract_harnesses_reject_preserves() {
+        let args: HarnessArgs = syn::parse_str("preserves = Self::is_valid").unwrap();
+        let func: syn::ItemFn = syn::parse_str("fn push(&mut self, x: u8) {}").unwrap();
+        assert!(check_harness_args(&args, &func.sig, false).is_ok());
+        assert_eq!(
+            check_harness_args(&args, &func.sig, true)
+                .unwrap_err()
+                .to_string(),
+            "`preserves` is not supported by `autokani_contract`, state the invariant in `ensures`"
+        );
+    }
+
+    /// The `Arbitrary` impl generated by `kani_arbitrary` with arguments `args` for `item`.
+    fn arbitrary_impl(args: &str, item: &str) -> String {
+        let args: ArbitraryArgs = syn::parse_str(args).unwrap();
+        match syn::parse_str(item).unwrap() {
+            Item::Struct(struct_def) => impl_arbitrary_via_fields(&struct_def, &args),
+            Item::Enum(enum_def) => impl_arbitrary_via_variants(&enum_def, &args),
+            _ => panic!("`{}` is neither a struct nor an enum", item),
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn enum_variants_are_picked_by_a_discriminant() {
+        let generated = arbitrary_impl("", "enum Shape { Dot, Circle(u8), Rect { w: u8, h: u8 } }");
+        let body = quote! {
+            match kani::any::<u8>() {
+                0 => Self::Dot,
+                1 => {
+                    let field_0: u8 = {
+                        let field_0: u8 = kani::any();
+                        field_0
+                    };
+                    Self::Circle(field_0)
+                },
+                _ => {
+                    let w: u8 = {
+                        let w: u8 = kani::any();
+                        w
+                    };
+                    let h: u8 = {
+                        let h: u8 = kani::any();
+                        h
+                    };
+                    Self::Rect { w, h }
+                }
+            }
+        };
+        assert!(generated.contains(&body.to_string()));
+        assert_eq!(
+            arbitrary_impl("", "enum Never {}"),
+            quote! {
+                compile_error! {
+                    "`kani_arbitrary` cannot generate a value of an enum without variants"
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn tuple_and_unit_structs() {
+        let generated = arbitrary_impl("", "struct Meters(u32, bool);");
+        let body = quote! {
             fn any() -> Self {
-                #(#init_code)*
-                Self::#func_name(#(#call_args),*)
+                {
+                    let field_0: u32 = {
+                        let field_0: u32 = kani::any();
+                        kani::assume(field_0 < 100000000);
+                        field_0
+                    };
+                    let field_1: bool = {
+                        let field_1: bool = kani::any();
+                        field_1
+                    };
+                    Self(field_0, field_1)
+                }
+            }
+        };
+        assert!(generated.contains(&body.to_string()));
+        let generated = arbitrary_impl("", "struct Marker;");
+        assert!(generated.contains(&quote! { fn any() -> Self { Self } }.to_string()));
+    }
+
+    #[test]
+    fn generic_structs_bound_their_field_parameters() {
+        let generated = arbitrary_impl(
+            "",
+            "struct Tagged<'a, T, U: Copy, const N: usize> { items: [T; N], tag: PhantomData<&'a U> }",
+        );
+        assert!(generated.contains(
+            &quote! {
+                impl<'a, T, U: Copy, const N: usize> kani::Arbitrary for Tagged<'a, T, U, N>
+                where
+                    T: kani::Arbitrary
+            }
+            .to_string()
+        ));
+    }
+
+    #[test]
+    fn reference_fields_are_leaked() {
+        let generated = arbitrary_impl("", "struct View<'a> { data: &'a [u8], name: &'a str }");
+        let data = quote! {
+            let data: &'a [u8] = {
+                let data_obj = kani::any::<[u8; 16usize]>();
+                kani::slice::any_slice_of_array(Box::leak(Box::new(data_obj)))
+            };
+        };
+        assert!(generated.contains(&data.to_string()));
+        assert!(generated.contains(&quote! { Box::leak(name_obj.into_boxed_str()) }.to_string()));
+        assert!(
+            arbitrary_impl("", "struct Maybe<'a> { value: Option<&'a u8> }").contains(
+                "compile_error ! { \"`kani_arbitrary` cannot generate a reference inside another type, \
+                 set this field with `#[autokani(value = ..)]` or `#[autokani(with = ..)]`\" }"
+            )
+        );
+    }
+
+    #[test]
+    fn field_attributes_refer_to_earlier_fields() {
+        let generated = arbitrary_impl(
+            "",
+            "struct Buffer {
+                #[autokani(range = 0..=16)]
+                capacity: usize,
+                #[autokani(range = 0..=capacity)]
+                len: usize,
+                #[autokani(value = Vec::with_capacity(capacity))]
+                data: Vec<u8>,
+                #[autokani(default)]
+                hits: u32,
+                #[autokani(with = gen::name)]
+                name: String,
+            }",
+        );
+        let body = quote! {
+            let capacity: usize = {
+                let capacity: usize = kani::any();
+                kani::assume((0..=16).contains(&capacity));
+                capacity
+            };
+            let len: usize = {
+                let len: usize = kani::any();
+                kani::assume((0..=capacity).contains(&len));
+                len
+            };
+            let data: Vec<u8> = Vec::with_capacity(capacity);
+            let hits: u32 = ::core::default::Default::default();
+            let name: String = gen::name();
+            Self { capacity, len, data, hits, name }
+        };
+        assert!(generated.contains(&body.to_string()));
+    }
+
+    #[test]
+    fn invariants_are_assumed_and_implemented() {
+        let generated = arbitrary_impl(
+            "invariant = Self::is_valid, impl_invariant",
+            "struct Array<T> { data: Vec<T>, len: usize }",
+        );
+        assert!(generated.contains(
+            &quote! {
+                kani::assume((Self::is_valid)(&value));
+                value
+            }
+            .to_string()
+        ));
+        assert!(generated.contains(
+            &quote! {
+                impl<T> kani::Invariant for Array<T>
+                where
+                    T: kani::Arbitrary
+                {
+                    fn is_safe(&self) -> bool {
+                        (Self::is_valid)(self)
+                    }
+                }
             }
+            .to_string()
+        ));
+        let generated = arbitrary_impl("invariant = Self::is_valid", "struct Flag(bool);");
+        assert!(!generated.contains("kani :: Invariant"));
+    }
+
+    #[test]
+    fn invariants_are_rechecked_on_mutated_values() {
+        let target = |sig: &str| {
+            let func: syn::ItemFn = syn::parse_str(&format!("{} {{}}", sig)).unwrap();
+            invariant_target(&func.sig).map(|target| target.to_string())
+        };
+        assert_eq!(
+            target("fn push(&mut self, x: u8)"),
+            Some(quote! { &*self_receiver }.to_string())
+        );
+        assert_eq!(
+            target("fn cleared(self) -> Self"),
+            Some(quote! { &result }.to_string())
+        );
+        assert_eq!(target("fn len(&self) -> usize"), None);
+        assert_eq!(target("fn into_vec(self) -> Vec<u8>"), None);
+        assert_eq!(target("fn new() -> Self"), None);
+
+        let args: HarnessArgs = syn::parse_str("preserves = Self::is_valid").unwrap();
+        let func: syn::ItemFn = syn::parse_str("fn len(&self) -> usize { 0 }").unwrap();
+        assert_eq!(
+            check_harness_args(&args, &func.sig, false)
+                .unwrap_err()
+                .to_string(),
+            "`preserves` needs a `&mut self` receiver, or a `self` receiver with `Self` returned"
+        );
+    }
+
+    /// The names of the constructors `extend_arbitrary` with `args` finds in `impl_block`.
+    fn constructors(args: &str, impl_block: &str) -> Result<Vec<String>, String> {
+        let args: ArbitraryArgs = syn::parse_str(args).unwrap();
+        let impl_block: ItemImpl = syn::parse_str(impl_block).unwrap();
+        let struct_name = match &*impl_block.self_ty {
+            Type::Path(type_path) => type_path.path.get_ident(),
+            _ => None,
+        };
+        find_constructors(&impl_block, struct_name, &args)
+            .map(|ctors| {
+                ctors
+                    .iter()
+                    .map(|ctor| ctor.sig.ident.to_string())
+                    .collect()
+            })
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn constructors_are_chosen() {
+        let impl_block = "impl Point {
+            pub fn x(&self) -> i32 { self.x }
+            pub fn origin() -> Point { Point { x: 0, y: 0 } }
+            pub fn new(x: i32, y: i32) -> Self { Point { x, y } }
+            pub fn scale(factor: i32) -> i32 { factor }
+        }";
+        assert_eq!(constructors("", impl_block).unwrap(), ["origin"]);
+        assert_eq!(constructors("ctor = new", impl_block).unwrap(), ["new"]);
+        assert_eq!(
+            constructors("ctors = [new, origin]", impl_block).unwrap(),
+            ["new", "origin"]
+        );
+        assert_eq!(
+            constructors("ctor = from_parts", impl_block).unwrap_err(),
+            "no method named `from_parts` in this impl block"
+        );
+        assert_eq!(
+            constructors("ctors = [new, x]", impl_block).unwrap_err(),
+            "`x` is not a constructor, it must take no `self` and return `Self`, \
+             `Result<Self, _>` or `Option<Self>`"
+        );
+        assert_eq!(
+            constructors("ctor = scale", impl_block).unwrap_err(),
+            "`scale` is not a constructor, it must take no `self` and return `Self`, \
+             `Result<Self, _>` or `Option<Self>`"
+        );
+        assert_eq!(
+            constructors("", "impl Point { pub fn x(&self) -> i32 { self.x } }").unwrap_err(),
+            "`extend_arbitrary` found no constructor in this impl block, \
+             add an associated function returning `Self`, `Result<Self, _>` or `Option<Self>`"
+        );
+    }
+
+    #[test]
+    fn constructors_are_called_with_arbitrary_arguments() {
+        let impl_block: ItemImpl = syn::parse_str(
+            "impl Point {
+                pub fn new(x: i32, y: i32) -> Self { Point { x, y } }
+                pub fn origin() -> Self { Point { x: 0, y: 0 } }
+            }",
+        )
+        .unwrap();
+        let args: ArbitraryArgs = syn::parse_str("ctors = [new, origin]").unwrap();
+        let generated = impl_arbitrary_via_constructor(&impl_block, &args).to_string();
+        assert!(generated.contains(&quote! { impl kani::Arbitrary for Point }.to_string()));
+        assert!(generated.contains(&quote! { Self::new(x, y) }.to_string()));
+        assert!(generated.contains(
+            &quote! {
+                _ => {
+                    Self::origin()
+                },
+            }
+            .to_string()
+        ));
+        assert!(generated.contains("match kani :: any :: < u8 > () { 0 =>"));
+    }
+
+    #[test]
+    fn fallible_constructors_are_assumed_to_succeed() {
+        let kind = |sig: &str| {
+            let method: ImplItemMethod = syn::parse_str(&format!("{} {{ todo!() }}", sig)).unwrap();
+            constructor_kind(&method, Some(&quote::format_ident!("Port")))
+        };
+        assert!(kind("fn new(n: u16) -> Port") == Some(CtorKind::Infallible));
+        assert!(kind("fn parse(s: &str) -> Result<Self, String>") == Some(CtorKind::Result));
+        assert!(kind("fn parse(s: &str) -> std::io::Result<Port>") == Some(CtorKind::Result));
+        assert!(kind("fn checked(n: u16) -> Option<Self>") == Some(CtorKind::Option));
+        assert!(kind("fn all() -> Vec<Self>").is_none());
+        assert!(kind("fn number(&self) -> Option<Self>").is_none());
+
+        let impl_block = "impl Port {
+            pub fn checked(n: u16) -> Option<Self> { todo!() }
+            pub fn parse(s: &str) -> Result<Self, String> { todo!() }
+            pub fn http() -> Self { Port(80) }
+        }";
+        // infallible constructors are preferred
+        assert_eq!(constructors("", impl_block).unwrap(), ["http"]);
+        assert_eq!(
+            constructors(
+                "",
+                "impl Port { pub fn checked(n: u16) -> Option<Self> { todo!() } }"
+            )
+            .unwrap(),
+            ["checked"]
+        );
+
+        let impl_block: ItemImpl = syn::parse_str(impl_block).unwrap();
+        let args: ArbitraryArgs = syn::parse_str("ctors = [parse, checked]").unwrap();
+        let generated = impl_arbitrary_via_constructor(&impl_block, &args).to_string();
+        assert!(generated.contains(
+            &quote! {
+                let constructed = Self::parse(s).ok();
+                kani::assume(constructed.is_some());
+                constructed.unwrap()
+            }
+            .to_string()
+        ));
+        assert!(generated.contains(
+            &quote! {
+                let constructed = Self::checked(n);
+                kani::assume(constructed.is_some());
+                constructed.unwrap()
+            }
+            .to_string()
+        ));
+    }
+
+    /// The names of the methods `extend_arbitrary` with `args` calls in the steps of `impl_block`.
+    fn mutators(args: &str, impl_block: &str) -> Result<Vec<String>, String> {
+        let args: ArbitraryArgs = syn::parse_str(args).unwrap();
+        let impl_block: ItemImpl = syn::parse_str(impl_block).unwrap();
+        find_mutators(&impl_block, Some(&quote::format_ident!("Stack")), &args)
+            .map(|methods| {
+                methods
+                    .iter()
+                    .map(|method| method.sig.ident.to_string())
+                    .collect()
+            })
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn steps_call_public_mutators() {
+        let impl_block = "impl Stack {
+            pub fn new() -> Self { Stack(Vec::new()) }
+            pub fn push(&mut self, x: u8) { self.0.push(x) }
+            pub fn pop(&mut self) -> Option<u8> { self.0.pop() }
+            pub fn len(&self) -> usize { self.0.len() }
+            fn reset(&mut self) { self.0.clear() }
+            pub unsafe fn set_len(&mut self, len: usize) { self.0.set_len(len) }
+            pub fn extend<I: IntoIterator<Item = u8>>(&mut self, items: I) { self.0.extend(items) }
+            pub fn append(&mut self, other: &mut Self) { self.0.append(&mut other.0) }
+            pub fn swap(&mut self, other: &mut Stack) { std::mem::swap(self, other) }
+            pub fn merge(&mut self, others: Vec<Stack>) {}
+        }";
+        assert_eq!(mutators("steps = 2", impl_block).unwrap(), ["push", "pop"]);
+        assert_eq!(
+            mutators("steps = 2, exclude = [pop]", impl_block).unwrap(),
+            ["push"]
+        );
+        assert_eq!(
+            mutators(
+                "steps = 2, include = [pop, push], exclude = [push]",
+                impl_block
+            )
+            .unwrap(),
+            ["pop"]
+        );
+        assert_eq!(
+            mutators("steps = 2, exclude = [clear]", impl_block).unwrap_err(),
+            "no method named `clear` in this impl block"
+        );
+        assert_eq!(
+            mutators("steps = 2, include = [len]", impl_block).unwrap_err(),
+            "`len` cannot be called in the steps, it must be a public and safe \
+             `&mut self` method without type parameters"
+        );
+        for method in ["append", "swap", "merge"] {
+            assert_eq!(
+                mutators(&format!("steps = 2, include = [{}]", method), impl_block).unwrap_err(),
+                format!(
+                    "`{}` cannot be called in the steps, generating its arguments of \
+                     type `Self` would make `kani::any` recurse without bound",
+                    method
+                )
+            );
         }
-    })
+        assert_eq!(
+            mutators("steps = 2, exclude = [push, pop]", impl_block).unwrap_err(),
+            "`steps` found no public `&mut self` method to call in this impl block"
+        );
+    }
+
+    #[test]
+    fn steps_pick_a_mutator_or_nothing() {
+        let impl_block: ItemImpl = syn::parse_str(
+            "impl Stack {
+                pub fn new() -> Self { Stack(Vec::new()) }
+                pub fn push(&mut self, x: u8) { self.0.push(x) }
+            }",
+        )
+        .unwrap();
+        let args: ArbitraryArgs = syn::parse_str("steps = 2").unwrap();
+        let generated = impl_arbitrary_via_constructor(&impl_block, &args).to_string();
+        let step = quote! {
+            match kani::any::<u8>() {
+                0 => {
+                    let x: u8 = kani::any();
+                    let _ = self_receiver.push(x);
+                }
+                _ => {}
+            }
+        }
+        .to_string();
+        assert_eq!(generated.matches(&step).count(), 2);
+        assert!(generated.contains(&quote! { let mut self_receiver: Self = }.to_string()));
+    }
+
+    /// The harnesses of `autokani_impl` with `args` on `impl_block`, or the error.
+    fn impl_harnesses(args: &str, impl_block: &str) -> Result<Vec<String>, String> {
+        let args: ImplArgs = syn::parse_str(args).unwrap();
+        let impl_block: ItemImpl = syn::parse_str(impl_block).unwrap();
+        let methods: Vec<_> = impl_block
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ImplItem::Method(method) => Some((method.clone(), None)),
+                _ => None,
+            })
+            .collect();
+        let owner = MethodOwner {
+            generics: &impl_block.generics,
+            self_ty: &impl_block.self_ty,
+            trait_path: None,
+            trait_items: Vec::new(),
+            harness_prefix: String::new(),
+            harness_suffix: String::new(),
+        };
+        harnesses_for_methods(&owner, &methods, &args)
+            .map(|harnesses| {
+                harnesses
+                    .iter()
+                    .map(|harness| harness.to_string())
+                    .collect()
+            })
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn unsupported_method_arguments_are_reported() {
+        let impl_block = "impl Wallet {
+            pub fn balance(&self) -> u64 { self.0 }
+            pub fn into_balance(self: Box<Self>) -> u64 { self.0 }
+            pub fn show(&self, out: &mut impl std::fmt::Write) {}
+            pub fn apply(&mut self, ops: Vec<Box<dyn Fn(u64) -> u64>>) {}
+            pub fn map(&mut self, f: fn(u64) -> u64) {}
+        }";
+        assert_eq!(
+            impl_harnesses("", impl_block).unwrap_err(),
+            "autokani cannot generate a typed `self` receiver for the harness of `into_balance`, \
+             leave the method out with `exclude = [into_balance]`"
+        );
+        assert_eq!(
+            impl_harnesses("exclude = [into_balance]", impl_block).unwrap_err(),
+            "autokani cannot generate an `impl Trait` argument for the harness of `show`, \
+             leave the method out with `exclude = [show]`"
+        );
+        assert_eq!(
+            impl_harnesses("include = [apply]", impl_block).unwrap_err(),
+            "autokani cannot generate a `dyn Trait` argument for the harness of `apply`, \
+             leave the method out with `exclude = [apply]`"
+        );
+        assert_eq!(
+            impl_harnesses("include = [map]", impl_block).unwrap_err(),
+            "autokani cannot generate a function pointer argument for the harness of `map`, \
+             leave the method out with `exclude = [map]`"
+        );
+        let harnesses =
+            impl_harnesses("exclude = [into_balance, show, apply, map]", impl_block).unwrap();
+        assert_eq!(harnesses.len(), 1);
+
+        let args: HarnessArgs = syn::parse_str("").unwrap();
+        let func: syn::ItemFn = syn::parse_str("fn show(x: impl Display) {}").unwrap();
+        assert_eq!(
+            check_harness_args(&args, &func.sig, false)
+                .unwrap_err()
+                .to_string(),
+            "autokani cannot generate an `impl Trait` argument for the harness of `show`"
+        );
+        let func: syn::ItemFn =
+            syn::parse_str("fn f(a: &[u8], b: (u8, Option<&str>), c: *const [u8; 4]) {}").unwrap();
+        assert!(unsupported_arg(&func.sig).is_none());
+    }
 }
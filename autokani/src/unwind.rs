@@ -0,0 +1,302 @@
+//! Selection of the `#[kani::unwind]` bound of generated harnesses.
+//!
+//! Unless the bound is given explicitly, it is derived from the largest collection
+//! the harness generates, so loops over the inputs are fully unwound. Values generated
+//! by their own `Arbitrary` impl, such as `self`, may hold collections of any size,
+//! so they keep the bound at least at the default one.
+use crate::attr::{ArgOptions, HarnessArgs, DEFAULT_UNWIND, DEFAULT_UNWIND_MARGIN};
+use crate::{ARR_LIMIT, STRING_LIMIT};
+use quote::quote;
+use syn::{
+    punctuated::Punctuated, token::Comma, Expr, ExprLit, FnArg, GenericArgument, Lit, Pat,
+    PathArguments, Receiver, Type, TypeArray, TypePath, TypeReference, TypeSlice, TypeTuple,
+};
+
+/// Types which never contain a generated collection.
+const SCALAR_TYPES: &[&str] = &[
+    "bool", "char", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128",
+    "isize", "f32", "f64",
+];
+
+/// The collections of a generated value.
+#[derive(Clone, Copy, Default)]
+struct Bound {
+    /// Number of elements of the largest collection generated by the harness, if any.
+    len: Option<usize>,
+    /// Whether the value holds a type generated by its own `Arbitrary` impl, e.g. `Self`,
+    /// whose collections may have any size (`#[autokani(len = N)]`, `extend_arbitrary`, ..).
+    opaque: bool,
+}
+
+impl Bound {
+    const OPAQUE: Bound = Bound {
+        len: None,
+        opaque: true,
+    };
+
+    fn len(len: usize) -> Self {
+        Bound {
+            len: Some(len),
+            opaque: false,
+        }
+    }
+
+    fn max(self, other: Bound) -> Bound {
+        Bound {
+            len: self.len.max(other.len),
+            opaque: self.opaque || other.opaque,
+        }
+    }
+}
+
+/// Bound on the collections generated for a type.
+trait CollectionBound {
+    fn bound(&self, opts: &ArgOptions) -> Bound;
+}
+
+impl CollectionBound for Receiver {
+    fn bound(&self, _opts: &ArgOptions) -> Bound {
+        Bound::OPAQUE
+    }
+}
+
+impl CollectionBound for TypePath {
+    fn bound(&self, opts: &ArgOptions) -> Bound {
+        if SCALAR_TYPES.iter().any(|ty| self.path.is_ident(ty)) {
+            return Bound::default();
+        }
+        if self.path.is_ident("String") || self.path.is_ident("str") {
+            return Bound::len(opts.len.unwrap_or(STRING_LIMIT));
+        }
+        let final_seg = match self.path.segments.last() {
+            Some(final_seg) => final_seg,
+            None => return Bound::default(),
+        };
+        let inner = match &final_seg.arguments {
+            PathArguments::AngleBracketed(args) => args
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty.bound(opts)),
+                    _ => None,
+                })
+                .fold(Bound::default(), Bound::max),
+            _ => Bound::default(),
+        };
+        if final_seg.ident == "Option" || final_seg.ident == "Result" {
+            inner
+        } else if final_seg.ident == "Vec" {
+            inner.max(Bound::len(opts.len.unwrap_or(ARR_LIMIT)))
+        } else if final_seg.ident == "PhantomData" {
+            Bound::default()
+        } else {
+            // user-defined types, generated by their own `Arbitrary` impl
+            inner.max(Bound::OPAQUE)
+        }
+    }
+}
+
+impl CollectionBound for TypeArray {
+    fn bound(&self, opts: &ArgOptions) -> Bound {
+        let elem = self.elem.bound(opts);
+        match &self.len {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }) => match len.base10_parse::<usize>() {
+                Ok(len) => elem.max(Bound::len(len)),
+                Err(_) => elem,
+            },
+            _ => elem,
+        }
+    }
+}
+
+impl CollectionBound for TypeSlice {
+    fn bound(&self, opts: &ArgOptions) -> Bound {
+        self.elem
+            .bound(opts)
+            .max(Bound::len(opts.len.unwrap_or(ARR_LIMIT)))
+    }
+}
+
+impl CollectionBound for TypeReference {
+    fn bound(&self, opts: &ArgOptions) -> Bound {
+        self.elem.bound(opts)
+    }
+}
+
+impl CollectionBound for TypeTuple {
+    fn bound(&self, opts: &ArgOptions) -> Bound {
+        self.elems
+            .iter()
+            .map(|elem| elem.bound(opts))
+            .fold(Bound::default(), Bound::max)
+    }
+}
+
+impl CollectionBound for Type {
+    fn bound(&self, opts: &ArgOptions) -> Bound {
+        match self {
+            Type::Path(type_path) => type_path.bound(opts),
+            Type::Array(type_arr) => type_arr.bound(opts),
+            Type::Slice(type_slice) => type_slice.bound(opts),
+            Type::Tuple(type_tuple) => type_tuple.bound(opts),
+            Type::Reference(type_ref) => type_ref.bound(opts),
+            Type::Paren(type_paren) => type_paren.elem.bound(opts),
+            _ => Bound::default(),
+        }
+    }
+}
+
+/// Generate the `#[kani::unwind]` attribute of a harness for a function with `inputs`,
+/// along with a doc comment recording how the bound was chosen.
+pub(crate) fn unwind_attr(
+    args: &HarnessArgs,
+    inputs: &Punctuated<FnArg, Comma>,
+) -> proc_macro2::TokenStream {
+    let (unwind, reason) = match args.unwind {
+        Some(unwind) => (unwind, "set by the `unwind` argument".to_string()),
+        None => {
+            let bounds: Vec<(String, Bound)> = inputs
+                .iter()
+                .map(|arg| match arg {
+                    FnArg::Receiver(receiver) => {
+                        ("self".to_string(), receiver.bound(&ArgOptions::default()))
+                    }
+                    FnArg::Typed(pat_type) => {
+                        let arg_name = match &*pat_type.pat {
+                            Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
+                            _ => "arg".to_string(),
+                        };
+                        let bound = pat_type.ty.bound(&args.arg_options(&arg_name));
+                        (arg_name, bound)
+                    }
+                })
+                .collect();
+            let derived = bounds
+                .iter()
+                .filter_map(|(arg_name, bound)| bound.len.map(|len| (arg_name, len)))
+                .max_by_key(|(_, len)| *len)
+                .map(|(arg_name, len)| {
+                    let margin = args.unwind_margin.unwrap_or(DEFAULT_UNWIND_MARGIN);
+                    (
+                        len + margin,
+                        format!(
+                            "the largest generated collection (`{}`) has up to {} elements, plus a margin of {}",
+                            arg_name, len, margin
+                        ),
+                    )
+                });
+            let opaque = bounds
+                .iter()
+                .find(|(_, bound)| bound.opaque)
+                .map(|(arg_name, _)| {
+                    (
+                        DEFAULT_UNWIND,
+                        format!(
+                            "`{}` is generated by its `Arbitrary` impl, whose collections \
+                             may have any size, default bound",
+                            arg_name
+                        ),
+                    )
+                });
+            match (derived, opaque) {
+                (Some(derived), Some(opaque)) if derived.0 > opaque.0 => derived,
+                (_, Some(opaque)) => opaque,
+                (Some(derived), None) => derived,
+                (None, None) => (
+                    DEFAULT_UNWIND,
+                    "no generated collection, default bound".to_string(),
+                ),
+            }
+        }
+    };
+    let unwind_lit = proc_macro2::Literal::usize_unsuffixed(unwind);
+    let doc = format!(" Unwind bound {}: {}.", unwind, reason);
+    quote! {
+        #[kani::unwind(#unwind_lit)]
+        #[doc = #doc]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse::Parser;
+
+    /// The bound and the doc line chosen for `func` with the harness arguments `args`.
+    fn unwind_of(args: &str, func: &str) -> (usize, String) {
+        let args: HarnessArgs = syn::parse_str(args).unwrap();
+        let func: syn::ItemFn = syn::parse_str(func).unwrap();
+        let attrs = syn::Attribute::parse_outer
+            .parse2(unwind_attr(&args, &func.sig.inputs))
+            .unwrap();
+        let unwind: syn::LitInt = attrs[0].parse_args().unwrap();
+        let doc = match attrs[1].parse_meta().unwrap() {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                lit: Lit::Str(doc), ..
+            }) => doc.value(),
+            _ => panic!("no doc line"),
+        };
+        (unwind.base10_parse().unwrap(), doc)
+    }
+
+    #[test]
+    fn explicit_bound() {
+        assert_eq!(
+            unwind_of("unwind = 8", "fn f(&self, v: Vec<u8>) {}"),
+            (
+                8,
+                " Unwind bound 8: set by the `unwind` argument.".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn bound_from_the_largest_collection() {
+        assert_eq!(
+            unwind_of("", "fn f(a: u32, v: Vec<u8>, s: String) {}"),
+            (
+                17,
+                " Unwind bound 17: the largest generated collection (`v`) has up to 16 elements, \
+                 plus a margin of 1."
+                    .to_string()
+            )
+        );
+        assert_eq!(unwind_of("len(s = 20)", "fn f(v: &[u8], s: &str) {}").0, 21);
+        assert_eq!(
+            unwind_of("len(v = 3)", "fn f(v: Option<Vec<(u8, [u8; 5])>>) {}").0,
+            6
+        );
+        assert_eq!(unwind_of("unwind_margin = 4", "fn f(v: Vec<u8>) {}").0, 20);
+    }
+
+    #[test]
+    fn default_bound_without_collections() {
+        assert_eq!(
+            unwind_of("", "fn f(a: u32, b: (bool, char), p: PhantomData<u8>) {}"),
+            (
+                64,
+                " Unwind bound 64: no generated collection, default bound.".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn default_bound_for_arbitrary_impls() {
+        assert_eq!(
+            unwind_of("", "fn f(&mut self, v: Vec<u8>) {}"),
+            (
+                64,
+                " Unwind bound 64: `self` is generated by its `Arbitrary` impl, whose collections \
+                 may have any size, default bound."
+                    .to_string()
+            )
+        );
+        assert_eq!(unwind_of("", "fn f(items: Option<Stack<u8>>) {}").0, 64);
+        assert_eq!(
+            unwind_of("len(v = 100)", "fn f(&self, v: Vec<u8>) {}").0,
+            101
+        );
+    }
+}
@@ -1,13 +1,13 @@
 // mod aaa;
 // mod backup;
 
-use autokani::{extend_arbitrary, kani_arbitrary, autokani_func, autokani_method};
+use autokani::{autokani_func, extend_arbitrary};
 pub struct Array {
     data: Vec<i32>,
     len: usize,
     capacity: usize,
 }
-#[extend_arbitrary]
+#[extend_arbitrary(invariant = Self::is_valid, impl_invariant, steps = 4)]
 impl Array {
     // #[autokani_method]
     pub fn new(cap: usize) -> Self {
@@ -18,6 +18,11 @@ impl Array {
         }
     }
 
+    pub fn is_valid(&self) -> bool {
+        self.len == self.data.len() && self.len <= self.capacity
+    }
+
+    #[autokani_func(ensures = self.len == old(self.len) + 1 || old(self.len) == self.capacity)]
     pub fn push(&mut self, val: i32) {
         if self.len == self.capacity {
             return;
@@ -34,6 +39,7 @@ impl Array {
     }
     /// # Safety
     /// This function is unsafe because it doesn't check if the index is out of bounds.
+    #[autokani_func(requires = index < self.len)]
     pub unsafe fn get_unchecked(&self, index: usize) -> i32 {
         *self.data.get_unchecked(index)
     }
@@ -41,7 +47,7 @@ impl Array {
     pub fn get_unsound(&self, index: usize) -> Option<i32> {
         Some(unsafe { self.get_unchecked(index) })
     }
-    #[autokani_func]
+    #[autokani_func(ensures = |ret| ret.is_some() == (index < self.len))]
     pub fn get_sound(&self, index: usize) -> Option<i32> {
         if index >= self.len {
             return None;
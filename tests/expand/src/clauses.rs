@@ -0,0 +1,79 @@
+//! `requires`, `ensures` and `old()` clauses checked by the harnesses themselves.
+use autokani::{autokani_func, kani_arbitrary};
+
+#[kani_arbitrary]
+pub struct Array {
+    data: [i32; 4],
+    len: usize,
+}
+
+impl Array {
+    /// # Safety
+    /// `index` must be less than `self.len`, which is at most 4.
+    #[autokani_func(requires = index < self.len, requires = self.len <= 4)]
+    pub unsafe fn get_unchecked(&self, index: usize) -> i32 {
+        *self.data.get_unchecked(index)
+    }
+}
+
+impl Array {
+    #[autokani_func(ensures = self.len == old(self.len) + 1 || old(self.len) >= 4)]
+    pub fn push(&mut self, val: i32) {
+        if self.len < 4 {
+            self.data[self.len] = val;
+            self.len += 1;
+        }
+    }
+}
+
+#[autokani_func(ensures = |ret| *ret == old(v.len()) && v.is_empty())]
+pub fn drain_all(v: &mut Vec<u8>) -> usize {
+    let n = v.len();
+    v.clear();
+    n
+}
+
+#[autokani_func(requires = divisor != 0)]
+pub fn div(dividend: u32, divisor: u32) -> u32 {
+    dividend / divisor
+}
+
+#[autokani_func(ensures = |ret| ret.is_some() == (index < 4))]
+pub fn lookup(table: [u8; 4], index: usize) -> Option<u8> {
+    table.get(index).copied()
+}
+
+#[autokani_func(ensures = result <= a && result <= b)]
+pub fn smaller(a: u8, b: u8) -> u8 {
+    a.min(b)
+}
+
+#[autokani_func(ensures = |ret| !*ret || !*flag)]
+pub fn take(flag: &mut bool) -> bool {
+    std::mem::replace(flag, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires() {
+        // the stub of Kani does not assume `requires`, so the arguments may violate them
+        let _: fn() = Array::check_get_unchecked;
+        let _: fn() = check_div;
+    }
+
+    #[test]
+    fn ensures() {
+        check_lookup();
+        check_smaller();
+        check_take();
+    }
+
+    #[test]
+    fn old_snapshots() {
+        Array::check_push();
+        check_drain_all();
+    }
+}
@@ -0,0 +1,104 @@
+//! Arbitrary impls of `extend_arbitrary` built through chosen constructors.
+use autokani::extend_arbitrary;
+
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[extend_arbitrary]
+impl Point {
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn origin() -> Point {
+        Point { x: 0, y: 0 }
+    }
+}
+
+pub struct Range {
+    pub start: u8,
+    pub end: u8,
+}
+
+#[extend_arbitrary(ctors = [new, empty], invariant = |range: &Self| range.start <= range.end)]
+impl Range {
+    pub fn empty() -> Self {
+        Range { start: 0, end: 0 }
+    }
+
+    pub fn new(start: u8, len: u8) -> Self {
+        Range {
+            start,
+            end: start.saturating_add(len),
+        }
+    }
+
+    pub fn singleton(at: u8) -> Self {
+        Range { start: at, end: at }
+    }
+}
+
+pub struct Celsius(pub i16);
+
+#[extend_arbitrary(ctor = from_tenths)]
+impl Celsius {
+    pub fn zero() -> Self {
+        Celsius(0)
+    }
+
+    pub fn from_tenths(tenths: i16) -> Self {
+        Celsius(tenths / 10)
+    }
+}
+
+pub struct Port(pub u16);
+
+#[extend_arbitrary]
+impl Port {
+    pub fn checked(number: u16) -> Option<Self> {
+        (number != 0).then_some(Port(number))
+    }
+}
+
+pub struct Even(pub u32);
+
+#[extend_arbitrary(ctors = [parse, halve])]
+impl Even {
+    pub fn parse(n: u32) -> Result<Self, String> {
+        match n % 2 {
+            0 => Ok(Even(n)),
+            _ => Err(format!("{} is odd", n)),
+        }
+    }
+
+    pub fn halve(n: u32) -> Option<Self> {
+        n.checked_mul(2).map(Even)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chosen_constructors() {
+        let point: Point = kani::any();
+        assert_eq!((point.x(), point.y), (0, 0));
+        let range: Range = kani::any();
+        assert!(range.start <= range.end);
+        let _ = Range::singleton(1);
+        let _ = Celsius::zero();
+        let celsius: Celsius = kani::any();
+        assert_eq!(celsius.0, 0);
+    }
+
+    #[test]
+    fn fallible_constructors() {
+        // the stub of Kani does not assume success, so `checked` may be given zero
+        let _: fn() -> Port = kani::any;
+        let even: Even = kani::any();
+        assert_eq!(even.0 % 2, 0);
+    }
+}
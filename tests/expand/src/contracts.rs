@@ -0,0 +1,64 @@
+//! Native Kani function contracts and their `proof_for_contract` harnesses,
+//! hoisted out of the impl for methods of `autokani_impl` blocks.
+use autokani::{autokani_contract, autokani_impl, kani_arbitrary};
+
+#[autokani_contract(requires = divisor != 0, ensures = |ret| *ret <= dividend)]
+pub fn div(dividend: u32, divisor: u32) -> u32 {
+    dividend / divisor
+}
+
+#[autokani_contract(
+    requires = *counter < u32::MAX,
+    ensures = *counter == old(*counter) + 1
+)]
+pub fn bump(counter: &mut u32) {
+    *counter += 1;
+}
+
+#[kani_arbitrary]
+pub struct Ratio {
+    num: u32,
+}
+
+#[autokani_impl]
+impl Ratio {
+    #[autokani_contract(requires = divisor != 0)]
+    pub fn scaled(&self, divisor: u32) -> u32 {
+        self.num / divisor
+    }
+}
+
+#[kani_arbitrary]
+pub struct Steps {
+    remaining: u8,
+}
+
+#[autokani_impl]
+impl Iterator for Steps {
+    type Item = u8;
+
+    #[autokani_contract(ensures = |ret| ret.is_none() || self.remaining < u8::MAX)]
+    fn next(&mut self) -> Option<u8> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        Some(self.remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contract_harnesses() {
+        // the stub of Kani does not assume `requires`, so the divisor may be zero
+        let _: fn() = check_div;
+        check_bump();
+    }
+
+    #[test]
+    fn hoisted_contract_harnesses() {
+        // the stub of Kani does not assume `requires`, so the divisor may be zero
+        let _: fn() = autokani_ratio::check_scaled;
+        autokani_steps_iterator::check_next();
+    }
+}
@@ -0,0 +1,31 @@
+//! `kani_arbitrary` on enums with unit, tuple and named variants.
+use autokani::{autokani_func, kani_arbitrary};
+
+#[kani_arbitrary]
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Reset,
+    Write(Vec<u8>, Option<u16>),
+    Rename { name: String, force: bool },
+}
+
+#[autokani_func]
+pub fn payload_len(command: Command) -> usize {
+    match command {
+        Command::Reset => 0,
+        Command::Write(bytes, _) => bytes.len(),
+        Command::Rename { name, .. } => name.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants() {
+        // the stub of Kani always picks the first variant
+        assert_eq!(kani::any::<Command>(), Command::Reset);
+        check_payload_len();
+    }
+}
@@ -0,0 +1,38 @@
+//! Methods whose arguments cannot be generated, left out of `autokani_impl` with `exclude`.
+use autokani::{autokani_impl, kani_arbitrary};
+use std::fmt::Write;
+use std::rc::Rc;
+
+#[kani_arbitrary]
+pub struct Wallet {
+    balance: u64,
+}
+
+#[autokani_impl(exclude = [share, show])]
+impl Wallet {
+    pub fn balance(&self) -> u64 {
+        self.balance
+    }
+
+    pub fn share(self: Rc<Self>) -> (Rc<Self>, Rc<Self>) {
+        (self.clone(), self)
+    }
+
+    pub fn show(&self, out: &mut impl Write) {
+        let _ = write!(out, "{}", self.balance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excluded_methods() {
+        autokani_wallet::check_balance();
+        let (wallet, _) = Rc::new(Wallet { balance: 3 }).share();
+        let mut out = String::new();
+        wallet.show(&mut out);
+        assert_eq!(wallet.balance().to_string(), out);
+    }
+}
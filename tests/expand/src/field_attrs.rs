@@ -0,0 +1,46 @@
+//! `#[autokani(..)]` attributes on the fields of `kani_arbitrary` types.
+use autokani::kani_arbitrary;
+
+#[kani_arbitrary]
+pub struct Buffer {
+    #[autokani(range = 0..=16)]
+    pub capacity: usize,
+    #[autokani(range = 0..=capacity)]
+    pub len: usize,
+    #[autokani(value = Vec::with_capacity(capacity))]
+    pub data: Vec<u8>,
+    #[autokani(len = 4, string = hex)]
+    pub id: String,
+    #[autokani(float = finite)]
+    pub load: f32,
+    #[autokani(default)]
+    pub hits: u32,
+    #[autokani(with = next_generation)]
+    pub generation: u64,
+}
+
+fn next_generation() -> u64 {
+    7
+}
+
+#[kani_arbitrary]
+pub enum Packet {
+    Data(
+        #[autokani(range = 1..=4)] u8,
+        #[autokani(value = vec![0; usize::from(field_0)])] Vec<u8>,
+    ),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_sources() {
+        let buffer: Buffer = kani::any();
+        assert_eq!((buffer.hits, buffer.generation), (0, 7));
+        assert!(buffer.data.capacity() >= buffer.capacity);
+        let Packet::Data(len, payload) = kani::any();
+        assert_eq!(payload.len(), usize::from(len));
+    }
+}
@@ -0,0 +1,30 @@
+//! Float domains per argument and harness-wide.
+use autokani::{autokani_func, kani_arbitrary};
+
+#[autokani_func(float(ratio = 0.0..=1.0), floats = finite)]
+pub fn scale(value: f32, ratio: f64) -> u8 {
+    (f64::from(value).abs().min(255.0) * ratio) as u8
+}
+
+#[autokani_func(float(x = not_nan, y = normal))]
+pub fn larger(x: f64, y: f64) -> f64 {
+    x.max(y)
+}
+
+#[kani_arbitrary(floats = finite)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_modes() {
+        check_scale();
+        check_larger();
+        let _: Point = kani::any();
+    }
+}
@@ -0,0 +1,55 @@
+//! Generic functions, instantiated explicitly or from the bounds of their parameters.
+use autokani::autokani_func;
+use std::ops::Add;
+
+#[autokani_func(instantiate(T = u8, T = i32, N = 4), ensures = |ret| arr.contains(ret))]
+pub fn largest<T: Ord + Copy, const N: usize>(arr: [T; N]) -> T {
+    let mut largest = arr[0];
+    for item in arr {
+        largest = largest.max(item);
+    }
+    largest
+}
+
+#[autokani_func(instantiate(T = u16), len(items = 4))]
+pub fn count_equal<T: PartialEq>(items: Vec<T>, item: T) -> usize {
+    items.iter().filter(|other| **other == item).count()
+}
+
+#[autokani_func]
+pub fn sum<T: Add<Output = T> + Copy>(a: T, b: T) -> T {
+    a + b
+}
+
+#[autokani_func(ensures = |ret| ret.0 <= ret.1)]
+pub fn sorted<T: Ord>(a: T, b: T) -> (T, T) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[autokani_func]
+pub fn duplicate<T: Clone>(item: T) -> (T, T) {
+    (item.clone(), item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_instantiations() {
+        check_largest_u8_4();
+        check_largest_i32_4();
+        check_count_equal_u16();
+    }
+
+    #[test]
+    fn instantiations_chosen_from_bounds() {
+        check_sum_i32();
+        check_sorted_u8();
+        check_duplicate_dummy();
+    }
+}
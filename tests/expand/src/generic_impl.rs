@@ -0,0 +1,63 @@
+//! Generic impl blocks and generic methods, instantiated from their bounds or explicitly.
+use autokani::{autokani_impl, kani_arbitrary};
+
+#[kani_arbitrary]
+pub struct Stack<T> {
+    items: Vec<T>,
+}
+
+#[autokani_impl]
+impl<T: Clone> Stack<T> {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn show<U: Clone>(&self, item: U) -> U {
+        item
+    }
+}
+
+#[kani_arbitrary]
+pub struct Wrapper<T> {
+    inner: T,
+}
+
+#[autokani_impl(instantiate(T = u8, T = i32))]
+impl<T: Copy + PartialOrd> Wrapper<T> {
+    pub fn get(&self) -> T {
+        self.inner
+    }
+
+    #[autokani_func(ensures = |ret| *ret >= Self::max_of(a, other))]
+    pub fn max_of(a: T, other: T) -> T {
+        if a < other {
+            other
+        } else {
+            a
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instantiated_from_bounds() {
+        autokani_stack_t::check_len_dummy();
+        autokani_stack_t::check_is_empty_dummy();
+        autokani_stack_t::check_show_dummy_dummy();
+    }
+
+    #[test]
+    fn instantiated_explicitly() {
+        autokani_wrapper_t::check_get_u8();
+        autokani_wrapper_t::check_get_i32();
+        autokani_wrapper_t::check_max_of_u8();
+        autokani_wrapper_t::check_max_of_i32();
+    }
+}
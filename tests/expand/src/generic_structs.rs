@@ -0,0 +1,72 @@
+//! `kani_arbitrary` and `extend_arbitrary` on types with generic parameters and lifetimes.
+use autokani::{autokani_func, extend_arbitrary, kani_arbitrary};
+use std::marker::PhantomData;
+
+#[kani_arbitrary]
+pub struct Pair<T> {
+    pub a: T,
+    pub b: T,
+}
+
+#[kani_arbitrary]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+#[kani_arbitrary]
+pub struct Fixed<T: Copy, const N: usize> {
+    pub items: [T; N],
+}
+
+#[kani_arbitrary]
+pub struct View<'a> {
+    pub data: &'a [u8],
+    pub name: &'a str,
+    pub first: &'a mut u16,
+    #[autokani(value = None)]
+    pub parent: Option<&'a View<'a>>,
+    pub marker: PhantomData<&'a u8>,
+}
+
+pub struct Slots<T> {
+    pub slots: Vec<T>,
+}
+
+#[extend_arbitrary]
+impl<T: Clone> Slots<T> {
+    pub fn filled(value: T, count: u8) -> Self {
+        Slots {
+            slots: vec![value; usize::from(count % 4)],
+        }
+    }
+}
+
+#[autokani_func]
+pub fn view_len(view: View<'_>, pair: Pair<u8>, either: Either<u8, bool>) -> usize {
+    let extra = match either {
+        Either::Left(left) => usize::from(left),
+        Either::Right(right) => usize::from(right),
+    };
+    view.data.len() + view.name.len() + usize::from(pair.a.max(pair.b)) + extra
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic_parameters() {
+        let _: Fixed<u8, 3> = kani::any();
+        let slots: Slots<u32> = kani::any();
+        assert!(slots.slots.len() < 4);
+        check_view_len();
+    }
+
+    #[test]
+    fn reference_fields() {
+        let view: View<'static> = kani::any();
+        *view.first += 1;
+        assert!(view.parent.is_none());
+    }
+}
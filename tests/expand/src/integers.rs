@@ -0,0 +1,47 @@
+//! Integer ranges per argument and harness-wide integer strategies.
+use autokani::{autokani_func, kani_arbitrary};
+
+#[autokani_func(range(index = 0..=255, n = ..))]
+pub fn pick(index: u32, n: u64, table: [u8; 4]) -> u8 {
+    table[index as usize % table.len()].wrapping_add(n as u8)
+}
+
+#[autokani_func(range(delta = -8..8), ensures = |ret| ret.is_some())]
+pub fn shift(base: i16, delta: i64) -> Option<i64> {
+    i64::from(base).checked_add(delta)
+}
+
+#[autokani_func(integers = boundary)]
+pub fn midpoint(lo: u32, pair: (i8, Option<u64>)) -> u64 {
+    let (offset, extra) = pair;
+    u64::from(lo) / 2 + offset.unsigned_abs() as u64 + extra.unwrap_or(0) / 2
+}
+
+#[autokani_func(integers = full)]
+pub fn wrapping_sum(a: i64, b: i64) -> i64 {
+    a.wrapping_add(b)
+}
+
+#[kani_arbitrary(integers = boundary)]
+pub struct Limits {
+    pub low: u32,
+    pub high: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        check_pick();
+        check_shift();
+    }
+
+    #[test]
+    fn strategies() {
+        check_midpoint();
+        check_wrapping_sum();
+        let _: Limits = kani::any();
+    }
+}
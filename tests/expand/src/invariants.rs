@@ -0,0 +1,73 @@
+//! Invariants assumed on generated values, and re-checked after the method harnesses.
+use autokani::{autokani_impl, extend_arbitrary, kani_arbitrary};
+
+#[kani_arbitrary(invariant = Self::is_valid, impl_invariant)]
+pub struct Array {
+    #[autokani(len = 4)]
+    data: Vec<i32>,
+    len: usize,
+    capacity: usize,
+}
+
+impl Array {
+    pub fn is_valid(&self) -> bool {
+        self.len == self.data.len() && self.len <= self.capacity
+    }
+}
+
+// without `preserves`, the `kani::Invariant` impl of `Array` is re-checked
+#[autokani_impl]
+impl Array {
+    pub fn push(&mut self, x: i32) {
+        if self.len < self.capacity {
+            self.data.push(x);
+            self.len += 1;
+        }
+    }
+
+    pub fn cleared(mut self) -> Self {
+        self.data.clear();
+        self.len = 0;
+        self
+    }
+
+    #[autokani_func(preserves = |array: &Self| array.len <= array.capacity)]
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.data.truncate(len);
+            self.len = len;
+        }
+    }
+}
+
+pub struct Counter {
+    count: u8,
+    limit: u8,
+}
+
+#[extend_arbitrary(invariant = |counter: &Self| counter.count <= counter.limit)]
+impl Counter {
+    pub fn new(limit: u8) -> Self {
+        Counter { count: 0, limit }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assumed_invariants() {
+        let array: Array = kani::any();
+        assert!(kani::Invariant::is_safe(&array));
+        let counter: Counter = kani::any();
+        assert!(counter.count <= counter.limit);
+    }
+
+    #[test]
+    fn invariants_rechecked() {
+        autokani_array::check_push();
+        autokani_array::check_cleared();
+        autokani_array::check_truncate();
+    }
+}
@@ -0,0 +1,27 @@
+//! Per-argument collection lengths, including nested collections.
+use autokani::autokani_func;
+
+#[autokani_func(len(buf = 64, items = 3), ensures = |ret| *ret <= 64)]
+pub fn fill(buf: &mut [u8], items: Option<Vec<u8>>) -> usize {
+    let items = items.unwrap_or_default();
+    for (slot, item) in buf.iter_mut().zip(items.iter().cycle()) {
+        *slot = *item;
+    }
+    buf.len()
+}
+
+#[autokani_func(len(name = 4), ensures = |ret| *ret <= 4)]
+pub fn name_len(name: Option<String>) -> usize {
+    name.map_or(0, |name| name.chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_argument_lengths() {
+        check_fill();
+        check_name_len();
+    }
+}
@@ -0,0 +1,25 @@
+//! Items using the autokani attributes, compiled against the stub of Kani in `tests/kani_stub`.
+//! Building this crate checks that the generated code compiles, and its tests run
+//! the generated harnesses whose arguments are valid with default values.
+// the instantiations chosen from bounds are reported through deprecation warnings
+#![allow(deprecated)]
+pub mod clauses;
+pub mod constructors;
+pub mod contracts;
+pub mod enums;
+pub mod excluded_methods;
+pub mod field_attrs;
+pub mod floats;
+pub mod generic_funcs;
+pub mod generic_impl;
+pub mod generic_structs;
+pub mod integers;
+pub mod invariants;
+pub mod lengths;
+pub mod multiple_impls;
+pub mod steps;
+pub mod strings;
+pub mod structs;
+pub mod trait_defaults;
+pub mod trait_impls;
+pub mod unwind;
@@ -0,0 +1,61 @@
+//! Several impl blocks of the same type in one module.
+use autokani::{autokani_impl, kani_arbitrary};
+
+#[kani_arbitrary]
+pub struct Counter {
+    count: u8,
+}
+
+#[autokani_impl]
+impl Counter {
+    pub fn get(&self) -> u8 {
+        self.count
+    }
+}
+
+#[autokani_impl(module = counter_updates, exclude = [reset])]
+impl Counter {
+    pub fn bump(&mut self) {
+        self.count = self.count.saturating_add(1);
+    }
+
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+#[kani_arbitrary]
+pub struct Cell<T> {
+    value: T,
+}
+
+#[autokani_impl]
+impl Cell<u8> {
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+}
+
+#[autokani_impl]
+impl Cell<u16> {
+    pub fn value(&self) -> u16 {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_modules() {
+        autokani_counter::check_get();
+        counter_updates::check_bump();
+    }
+
+    #[test]
+    fn modules_named_after_generic_arguments() {
+        autokani_cell_u8::check_value();
+        autokani_cell_u16::check_value();
+    }
+}
@@ -0,0 +1,73 @@
+//! Values of `extend_arbitrary` reached through a sequence of method calls after construction.
+use autokani::extend_arbitrary;
+
+pub struct Stack {
+    items: Vec<u8>,
+}
+
+#[extend_arbitrary(steps = 3, exclude = [clear])]
+impl Stack {
+    pub fn empty() -> Self {
+        Stack { items: Vec::new() }
+    }
+
+    pub fn push(&mut self, item: u8) {
+        if self.items.len() < 8 {
+            self.items.push(item);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<u8> {
+        self.items.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear()
+    }
+
+    // skipped, generating `other` would recurse without bound
+    pub fn append(&mut self, other: &mut Self) {
+        self.items.append(&mut other.items)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.items.len()
+    }
+}
+
+pub struct Gauge {
+    level: u8,
+}
+
+#[extend_arbitrary(steps = 2, include = [raise], invariant = |gauge: &Self| gauge.level <= 10)]
+impl Gauge {
+    pub fn empty() -> Self {
+        Gauge { level: 0 }
+    }
+
+    pub fn raise(&mut self, by: u8) {
+        self.level = self.level.saturating_add(by).min(10);
+    }
+
+    pub fn drain(&mut self) {
+        self.level = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_after_steps() {
+        // the stub of Kani picks the first method for every step
+        let mut stack: Stack = kani::any();
+        assert_eq!(stack.depth(), 3);
+        stack.append(&mut Stack::empty());
+        stack.pop();
+        stack.clear();
+        let mut gauge: Gauge = kani::any();
+        assert!(gauge.level <= 10);
+        gauge.drain();
+    }
+}
@@ -0,0 +1,27 @@
+//! Strings of variable length, with restricted characters or from UTF-8 bytes.
+use autokani::autokani_func;
+
+#[autokani_func(
+    string(name = ascii, id = "0-9a-f", raw = utf8),
+    len(id = 4),
+    ensures = |ret| *ret <= 8 + 4 + 8
+)]
+pub fn total_len(name: String, id: &str, raw: String) -> usize {
+    name.len() + id.len() + raw.len()
+}
+
+#[autokani_func(string(code = digit))]
+pub fn parse_code(code: &str) -> Option<u32> {
+    code.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_modes() {
+        check_total_len();
+        check_parse_code();
+    }
+}
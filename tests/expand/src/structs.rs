@@ -0,0 +1,31 @@
+//! `kani_arbitrary` on named, tuple and unit structs.
+use autokani::{autokani_func, kani_arbitrary};
+
+#[kani_arbitrary]
+#[derive(Debug, PartialEq)]
+pub struct Meters(pub u32);
+
+#[kani_arbitrary]
+#[derive(Debug, PartialEq)]
+pub struct Span(pub Meters, pub Option<String>);
+
+#[kani_arbitrary]
+#[derive(Debug, PartialEq)]
+pub struct Marker;
+
+#[autokani_func]
+pub fn total(span: Span, _marker: Marker) -> u64 {
+    u64::from((span.0).0) + span.1.map_or(0, |label| label.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuple_and_unit_structs() {
+        assert_eq!(kani::any::<Marker>(), Marker);
+        let _: Span = kani::any();
+        check_total();
+    }
+}
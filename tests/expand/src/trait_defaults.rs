@@ -0,0 +1,76 @@
+//! Default methods of traits, checked for each listed implementor.
+use autokani::{autokani_trait, kani_arbitrary};
+
+#[autokani_trait(impls = [VecStore, SlotStore<u8>], exclude = [describe])]
+pub trait Store {
+    const CAPACITY: usize;
+
+    fn get(&self, key: u8) -> Option<u32>;
+
+    #[autokani_func(ensures = |ret| *ret == self.get(key).is_some())]
+    fn contains(&self, key: u8) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn capacity() -> usize {
+        Self::CAPACITY
+    }
+
+    // an `impl Trait` argument cannot be generated
+    fn describe(&self, out: &mut impl std::fmt::Write) {
+        let _ = write!(out, "{} slots", Self::CAPACITY);
+    }
+}
+
+#[kani_arbitrary]
+pub struct VecStore {
+    data: Vec<u32>,
+}
+
+impl Store for VecStore {
+    const CAPACITY: usize = 16;
+
+    fn get(&self, key: u8) -> Option<u32> {
+        self.data.get(usize::from(key)).copied()
+    }
+}
+
+#[kani_arbitrary]
+pub struct SlotStore<T> {
+    slot: T,
+}
+
+impl Store for SlotStore<u8> {
+    const CAPACITY: usize = 1;
+
+    fn get(&self, key: u8) -> Option<u32> {
+        (key == self.slot).then_some(u32::from(key))
+    }
+}
+
+#[autokani_trait(impls = [u8])]
+pub trait Echo<T: Clone> {
+    fn echo(&self, value: T) -> T {
+        value
+    }
+}
+
+impl<T: Clone> Echo<T> for u8 {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_methods_per_implementor() {
+        check_Store_contains_VecStore();
+        check_Store_contains_SlotStore_u8();
+        check_Store_capacity_VecStore();
+        check_Store_capacity_SlotStore_u8();
+    }
+
+    #[test]
+    fn generic_trait() {
+        check_Echo_echo_u8_dummy();
+    }
+}
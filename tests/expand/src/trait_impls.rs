@@ -0,0 +1,95 @@
+//! Trait impls, whose harnesses call `<Type as Trait>::method`.
+use autokani::{autokani_impl, kani_arbitrary};
+use std::io::Read;
+use std::ops::{Add, Index};
+
+#[kani_arbitrary]
+pub struct Countdown {
+    left: u8,
+}
+
+#[autokani_impl]
+impl Iterator for Countdown {
+    type Item = u8;
+
+    #[autokani_func(ensures = |ret: &Option<Self::Item>| ret.is_some() == (old(self.left) > 0))]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.left = self.left.checked_sub(1)?;
+        Some(self.left)
+    }
+}
+
+#[kani_arbitrary]
+pub struct Meters {
+    value: u32,
+}
+
+#[autokani_impl]
+impl Add for Meters {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Meters {
+            value: self.value.saturating_add(rhs.value),
+        }
+    }
+}
+
+#[autokani_impl]
+impl Add<u32> for Meters {
+    type Output = Meters;
+
+    fn add(self, rhs: u32) -> Meters {
+        Meters {
+            value: self.value.saturating_add(rhs),
+        }
+    }
+}
+
+#[kani_arbitrary]
+pub struct Bytes {
+    data: Vec<u8>,
+}
+
+#[autokani_impl]
+impl Index<usize> for Bytes {
+    type Output = u8;
+
+    #[autokani_func(requires = index < self.data.len())]
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.data[index]
+    }
+}
+
+#[autokani_impl]
+impl Read for Bytes {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data.drain(..n);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iterator() {
+        autokani_countdown_iterator::check_next();
+    }
+
+    #[test]
+    fn operators() {
+        autokani_meters_add::check_add();
+        autokani_meters_add_u32::check_add();
+        // the stub of Kani does not assume `requires`, so the index may be out of bounds
+        let _: fn() = autokani_bytes_index_usize::check_index;
+    }
+
+    #[test]
+    fn read() {
+        autokani_bytes_read::check_read();
+    }
+}
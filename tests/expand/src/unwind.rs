@@ -0,0 +1,43 @@
+//! The `#[kani::unwind]` bound of the harnesses, given explicitly or derived from the arguments.
+use autokani::{autokani_func, autokani_impl, kani_arbitrary};
+
+#[autokani_func(unwind = 8)]
+pub fn count_set(bits: [bool; 4]) -> usize {
+    bits.iter().filter(|bit| **bit).count()
+}
+
+#[autokani_func(len(items = 3), unwind_margin = 2)]
+pub fn total(items: Vec<u8>, name: String) -> usize {
+    items.iter().map(|item| usize::from(*item)).sum::<usize>() + name.len()
+}
+
+#[kani_arbitrary]
+pub struct Frame {
+    #[autokani(len = 64)]
+    payload: Vec<u8>,
+}
+
+#[autokani_impl]
+impl Frame {
+    pub fn checksum(&self) -> u8 {
+        self.payload
+            .iter()
+            .fold(0, |acc: u8, byte| acc.wrapping_add(*byte))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_bound() {
+        check_count_set();
+    }
+
+    #[test]
+    fn derived_bound() {
+        check_total();
+        autokani_frame::check_checksum();
+    }
+}
@@ -0,0 +1,81 @@
+//! Stub of the Kani library, enough to compile and run the generated harnesses
+//! with default values instead of symbolic ones.
+pub use kani_macros::*;
+
+pub trait Arbitrary {
+    fn any() -> Self;
+}
+
+pub trait Invariant {
+    fn is_safe(&self) -> bool;
+}
+
+macro_rules! arbitrary_default {
+    ($($ty:ty),*) => {
+        $(
+            impl Arbitrary for $ty {
+                fn any() -> Self {
+                    Default::default()
+                }
+            }
+        )*
+    };
+}
+
+arbitrary_default!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    bool,
+    char,
+    ()
+);
+
+impl<T: Arbitrary, const N: usize> Arbitrary for [T; N] {
+    fn any() -> Self {
+        std::array::from_fn(|_| T::any())
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn any() -> Self {
+        Some(T::any())
+    }
+}
+
+pub fn any<T: Arbitrary>() -> T {
+    T::any()
+}
+
+pub fn assume(_cond: bool) {}
+
+pub fn assert(cond: bool, msg: &'static str) {
+    assert!(cond, "{}", msg);
+}
+
+pub mod vec {
+    pub fn any_vec<T: super::Arbitrary, const MAX_LENGTH: usize>() -> Vec<T> {
+        Vec::new()
+    }
+}
+
+pub mod slice {
+    pub fn any_slice_of_array<T, const N: usize>(arr: &[T; N]) -> &[T] {
+        &arr[..]
+    }
+
+    pub fn any_slice_of_array_mut<T, const N: usize>(arr: &mut [T; N]) -> &mut [T] {
+        &mut arr[..]
+    }
+}